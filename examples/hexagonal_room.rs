@@ -0,0 +1,52 @@
+use std::{f64::consts::PI, io};
+
+use rt::prelude::*;
+
+fn hexagonal_room_scene() -> Result<Scene> {
+    let material = Material {
+        color: Color::new(1.0, 0.9, 0.9),
+        specular: 0.0,
+        ..Default::default()
+    };
+    let floor = Plane::new(Matrice::identity_matrix(4), material.clone())?;
+    let first_wall = Plane::new(
+        rotation_y(PI / 3.0) * translation(0.0, 0.0, 6.0) * rotation_x(PI / 2.0),
+        material.clone(),
+    )?;
+    let second_wall = Plane::new(
+        rotation_y((2.0 * PI) / 3.0) * translation(0.0, 0.0, 6.0) * rotation_x(PI / 2.0),
+        material.clone(),
+    )?;
+    let third_wall = Plane::new(
+        rotation_y(PI / 3.0) * translation(0.0, 0.0, 6.0) * rotation_x(PI / 2.0),
+        material.clone(),
+    )?;
+    let fourth_wall = Plane::new(
+        rotation_y(4.0 * PI / 3.0) * translation(0.0, 0.0, 6.0) * rotation_x(PI / 2.0),
+        material.clone(),
+    )?;
+    let mut world = World::new(Light::new(Point::new(-10.0, 10.0, -10.0), WHITE));
+    world.add_shape(Box::new(floor));
+    world.add_shape(Box::new(first_wall));
+    world.add_shape(Box::new(second_wall));
+    world.add_shape(Box::new(third_wall));
+    world.add_shape(Box::new(fourth_wall));
+    let mut camera = Camera::new(100.0, 100.0, PI / 6.0);
+    camera.set_transform(view_transformation(
+        Point::new(0.0, 30.0, 0.0),
+        Point::new(2.0, 1.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    ))?;
+    Ok(Scene {
+        world,
+        camera,
+        animation: Default::default(),
+        files: vec![],
+    })
+}
+
+fn main() -> Result<()> {
+    let Scene { world, camera, .. } = hexagonal_room_scene()?;
+    camera.render(&world)?.write_ppm(&mut io::stdout().lock())?;
+    Ok(())
+}
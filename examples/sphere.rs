@@ -0,0 +1,80 @@
+use std::{f64::consts::PI, io};
+
+use rt::{materials::Stripe, prelude::*};
+
+fn sphere_scene() -> Result<Scene> {
+    let material = Material {
+        color: Color::new(1.0, 0.9, 0.9),
+        specular: 0.0,
+        ..Default::default()
+    };
+    let floor = Plane::new(Matrice::identity_matrix(4), material.clone())?;
+    let left_wall = Plane::new(
+        translation(0.0, 0.0, 6.0) * rotation_y(PI / 4.0) * rotation_x(PI / 2.0),
+        material.clone(),
+    )?;
+    let right_wall = Plane::new(
+        translation(0.0, 0.0, 6.0) * rotation_y(-PI / 4.0) * rotation_x(PI / 2.0),
+        material.clone(),
+    )?;
+    let middle = Sphere::new(
+        translation(-0.5, 1.0, 0.5),
+        Material {
+            color: Color::new(0.1, 1.0, 0.5),
+            diffuse: 0.7,
+            specular: (0.3),
+            pattern: Some(Pattern::Stripe(Stripe::new(
+                BLACK,
+                WHITE,
+                scaling(0.5, 0.5, 0.5),
+            )?)),
+            ..Default::default()
+        },
+    )?;
+    let right = Sphere::new(
+        translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5),
+        Material {
+            color: Color::new(0.5, 1.0, 0.1),
+            diffuse: 0.7,
+            specular: 0.3,
+            ..Default::default()
+        },
+    )?;
+    let left = Sphere::new(
+        translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33),
+        Material {
+            color: Color::new(1.0, 0.8, 0.1),
+            diffuse: 0.7,
+            specular: 0.3,
+            ..Default::default()
+        },
+    )?;
+    //add shapes function
+    let mut world = World::new(Light::new(Point::new(-10.0, 10.0, -10.0), WHITE));
+    world.add_shapes(vec![
+        Box::new(floor),
+        Box::new(right_wall),
+        Box::new(left_wall),
+        Box::new(middle),
+        Box::new(right),
+        Box::new(left),
+    ]);
+    let mut camera = Camera::new(300.0, 200.0, PI / 4.0);
+    camera.set_transform(view_transformation(
+        Point::new(1.0, 1.0, -4.0),
+        Point::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    ))?;
+    Ok(Scene {
+        world,
+        camera,
+        animation: Default::default(),
+        files: vec![],
+    })
+}
+
+fn main() -> Result<()> {
+    let Scene { world, camera, .. } = sphere_scene()?;
+    camera.render(&world)?.write_ppm(&mut io::stdout().lock())?;
+    Ok(())
+}
@@ -0,0 +1,704 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use rt::camera::{Aov, Tile, TileOrder};
+
+pub const USAGE: &str = "usage: rt [OPTIONS] <SCENE>
+       rt --worker <ADDR> [-j <N>] [-q]
+
+Renders a scene file and writes the image, or renders tiles for a coordinator.
+
+options:
+  -o, --output <PATH>     output file, '-' for stdout (default: stdout)
+  -f, --format <FORMAT>   image format: ppm or bmp (default: from output extension, else ppm)
+  -W, --width <PIXELS>    override the camera width
+  -H, --height <PIXELS>   override the camera height
+      --fov <DEGREES>     override the camera field of view
+  -s, --samples <N>       override the samples per pixel
+  -i, --integrator <NAME> shading: whitted, path or ao (default: from the scene)
+      --seed <N>          random seed for everything sampled; equal seeds give equal
+                          images whatever the number of threads (default: from the scene)
+      --time <SEC>        render an animated scene as it is at this time (default: as written)
+      --frames <START>..<END> render every frame from START to END seconds, numbering the
+                          output files: a run of '#' in a name becomes the frame number,
+                          otherwise it goes before the extension
+      --fps <N>           frames per second of --frames (default: 24)
+      --shutter <SEC>     keep the shutter open this long from each frame's time (or
+                          from 0), blurring animated shapes across the samples
+      --aov <PASS>=<PATH> also write an output pass: depth, normal, albedo, shadow or
+                          object-id (repeatable)
+      --region <X,Y,W,H>  only render this rectangle of the image, as a smaller image
+      --full-frame        place the region in a full size image instead, black elsewhere
+      --checkpoint <PATH> save samples to PATH as the render goes, resuming from it when
+                          it holds an earlier render of the same scene, images and camera
+      --checkpoint-every <SEC> seconds between checkpoint saves (default: 60)
+      --tile-size <N>     edge of the square tiles rendered as a unit (default: 32)
+      --tile-order <NAME> tile order: spiral, hilbert or scanline (default: spiral)
+      --progressive       render one sample per pass over the whole image, rewriting
+                          the output file after every pass
+      --time-budget <SEC> stop starting new tiles after this long and keep what is done
+      --sample-budget <N> most samples per pixel; progressive renders refine up to it
+  -j, --threads <N>       number of render threads (default: all cores)
+      --serve <ADDR>      listen on ADDR and have workers render the tiles; cannot be
+                          combined with --aov, --progressive, --checkpoint or --time-budget
+      --farm-timeout <SEC> give up on a worker after this long on one tile, and on the
+                          render when no tile comes back for as long (default: 600)
+      --worker <ADDR>     render tiles for the coordinator at ADDR until it is done
+      --no-progress       hide the progress bar
+  -q, --quiet             do not print render statistics
+  -h, --help              print this help";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Ppm,
+    Bmp,
+}
+
+impl Format {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "ppm" => Ok(Format::Ppm),
+            "bmp" => Ok(Format::Bmp),
+            _ => Err(format!(
+                "unsupported format '{}' (expected ppm or bmp)",
+                value
+            )),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Format::parse(path.extension()?.to_str()?).ok()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IntegratorKind {
+    Whitted,
+    Path,
+    AmbientOcclusion,
+}
+
+impl IntegratorKind {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "whitted" => Ok(IntegratorKind::Whitted),
+            "path" => Ok(IntegratorKind::Path),
+            "ao" => Ok(IntegratorKind::AmbientOcclusion),
+            _ => Err(format!(
+                "unknown integrator '{}' (expected whitted, path or ao)",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub scene: PathBuf,
+    pub output: Option<PathBuf>,
+    pub format: Format,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub fov: Option<f64>,
+    pub samples: Option<usize>,
+    pub integrator: Option<IntegratorKind>,
+    pub seed: Option<u64>,
+    pub time: Option<f64>,
+    pub frames: Option<(f64, f64)>,
+    pub fps: f64,
+    pub shutter: Option<f64>,
+    pub aovs: Vec<(Aov, PathBuf)>,
+    pub region: Option<Tile>,
+    pub full_frame: bool,
+    pub checkpoint: Option<PathBuf>,
+    pub checkpoint_interval: Option<Duration>,
+    pub tile_size: Option<usize>,
+    pub tile_order: Option<TileOrder>,
+    pub progressive: bool,
+    pub time_budget: Option<Duration>,
+    pub sample_budget: Option<usize>,
+    pub threads: Option<usize>,
+    pub serve: Option<String>,
+    pub farm_timeout: Option<Duration>,
+    pub progress: bool,
+    pub stats: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Render(Box<Options>),
+    Worker {
+        address: String,
+        threads: Option<usize>,
+        stats: bool,
+    },
+    Help,
+}
+
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let mut scene = None;
+    let mut output = None;
+    let mut format = None;
+    let mut width = None;
+    let mut height = None;
+    let mut fov = None;
+    let mut samples = None;
+    let mut integrator = None;
+    let mut seed = None;
+    let mut time = None;
+    let mut frames = None;
+    let mut fps = 24.0;
+    let mut shutter = None;
+    let mut aovs = vec![];
+    let mut region = None;
+    let mut full_frame = false;
+    let mut checkpoint = None;
+    let mut checkpoint_interval = None;
+    let mut tile_size = None;
+    let mut tile_order = None;
+    let mut progressive = false;
+    let mut time_budget = None;
+    let mut sample_budget = None;
+    let mut threads = None;
+    let mut serve = None;
+    let mut farm_timeout = None;
+    let mut worker = None;
+    let mut progress = true;
+    let mut stats = true;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-o" | "--output" => {
+                let value = value(&mut args, &arg)?;
+                output = if value == "-" {
+                    None
+                } else {
+                    Some(PathBuf::from(value))
+                };
+            }
+            "-f" | "--format" => format = Some(Format::parse(&value(&mut args, &arg)?)?),
+            "-W" | "--width" => width = Some(positive(&value(&mut args, &arg)?, &arg)?),
+            "-H" | "--height" => height = Some(positive(&value(&mut args, &arg)?, &arg)?),
+            "-s" | "--samples" => samples = Some(positive(&value(&mut args, &arg)?, &arg)?),
+            "-i" | "--integrator" => {
+                integrator = Some(IntegratorKind::parse(&value(&mut args, &arg)?)?)
+            }
+            "--seed" => {
+                let value = value(&mut args, &arg)?;
+                seed = Some(value.parse::<u64>().map_err(|_| {
+                    format!("{} expects a non-negative integer, found '{}'", arg, value)
+                })?);
+            }
+            "--time" => {
+                let value = value(&mut args, &arg)?;
+                match value.parse::<f64>() {
+                    Ok(t) if t.is_finite() => time = Some(t),
+                    _ => return Err(format!("{} expects a number, found '{}'", arg, value)),
+                }
+            }
+            "--frames" => {
+                let value = value(&mut args, &arg)?;
+                let range = value
+                    .split_once("..")
+                    .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)));
+                match range {
+                    Some((start, end)) if start <= end && f64::is_finite(end - start) => {
+                        frames = Some((start, end))
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{} expects <start>..<end> in seconds, found '{}'",
+                            arg, value
+                        ))
+                    }
+                }
+            }
+            "--fps" => {
+                let value = value(&mut args, &arg)?;
+                match value.parse::<f64>() {
+                    Ok(n) if n > 0.0 && n.is_finite() => fps = n,
+                    _ => {
+                        return Err(format!(
+                            "{} expects a positive number, found '{}'",
+                            arg, value
+                        ))
+                    }
+                }
+            }
+            "--shutter" => {
+                let value = value(&mut args, &arg)?;
+                match value.parse::<f64>() {
+                    Ok(n) if n > 0.0 && n.is_finite() => shutter = Some(n),
+                    _ => {
+                        return Err(format!(
+                            "{} expects a positive number, found '{}'",
+                            arg, value
+                        ))
+                    }
+                }
+            }
+            "--aov" => {
+                let value = value(&mut args, &arg)?;
+                let (name, path) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("{} expects <pass>=<path>, found '{}'", arg, value))?;
+                let aov = Aov::parse(name).map_err(|e| e.to_string())?;
+                aovs.push((aov, PathBuf::from(path)));
+            }
+            "--region" => {
+                let value = value(&mut args, &arg)?;
+                let numbers: Vec<usize> = value
+                    .split(',')
+                    .map(|n| n.trim().parse::<usize>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("{} expects X,Y,W,H, found '{}'", arg, value))?;
+                match numbers[..] {
+                    [x, y, width, height] if width > 0 && height > 0 => {
+                        region = Some(Tile {
+                            x,
+                            y,
+                            width,
+                            height,
+                        })
+                    }
+                    _ => return Err(format!("{} expects X,Y,W,H, found '{}'", arg, value)),
+                }
+            }
+            "--full-frame" => full_frame = true,
+            "--tile-size" => tile_size = Some(positive(&value(&mut args, &arg)?, &arg)?),
+            "--tile-order" => {
+                let value = value(&mut args, &arg)?;
+                tile_order = Some(match value.as_str() {
+                    "spiral" => TileOrder::Spiral,
+                    "hilbert" => TileOrder::Hilbert,
+                    "scanline" => TileOrder::Scanline,
+                    _ => {
+                        return Err(format!(
+                            "unknown tile order '{}' (expected spiral, hilbert or scanline)",
+                            value
+                        ))
+                    }
+                });
+            }
+            "--progressive" => progressive = true,
+            "--time-budget" => time_budget = Some(seconds(&value(&mut args, &arg)?, &arg)?),
+            "--checkpoint" => checkpoint = Some(PathBuf::from(value(&mut args, &arg)?)),
+            "--checkpoint-every" => {
+                checkpoint_interval = Some(seconds(&value(&mut args, &arg)?, &arg)?)
+            }
+            "--sample-budget" => sample_budget = Some(positive(&value(&mut args, &arg)?, &arg)?),
+            "-j" | "--threads" => threads = Some(positive(&value(&mut args, &arg)?, &arg)?),
+            "--serve" => serve = Some(value(&mut args, &arg)?),
+            "--farm-timeout" => farm_timeout = Some(seconds(&value(&mut args, &arg)?, &arg)?),
+            "--worker" => worker = Some(value(&mut args, &arg)?),
+            "--fov" => {
+                let value = value(&mut args, &arg)?;
+                match value.parse::<f64>() {
+                    Ok(v) if v > 0.0 && v < 180.0 => fov = Some(v),
+                    _ => {
+                        return Err(format!(
+                            "{} expects an angle between 0 and 180 degrees, found '{}'",
+                            arg, value
+                        ))
+                    }
+                }
+            }
+            "--no-progress" => progress = false,
+            "-q" | "--quiet" => stats = false,
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option '{}'", arg))
+            }
+            _ => {
+                if scene.is_some() {
+                    return Err("only one scene can be rendered at a time".to_string());
+                }
+                scene = Some(PathBuf::from(arg));
+            }
+        }
+    }
+    if let Some(address) = worker {
+        if scene.is_some() {
+            return Err("a worker gets its scene from the coordinator".to_string());
+        }
+        return Ok(Command::Worker {
+            address,
+            threads,
+            stats,
+        });
+    }
+    let scene = scene.ok_or("no scene file given")?;
+    if serve.is_some()
+        && (!aovs.is_empty() || progressive || checkpoint.is_some() || time_budget.is_some())
+    {
+        return Err(
+            "--serve cannot be combined with --aov, --progressive, --checkpoint or --time-budget"
+                .to_string(),
+        );
+    }
+    if farm_timeout.is_some() && serve.is_none() {
+        return Err("--farm-timeout needs --serve".to_string());
+    }
+    if frames.is_some() {
+        if time.is_some() {
+            return Err("--time and --frames cannot be combined".to_string());
+        }
+        if output.is_none() {
+            return Err("--frames needs an output file to number".to_string());
+        }
+        if serve.is_some() || checkpoint.is_some() || progressive {
+            return Err(
+                "--frames cannot be combined with --serve, --checkpoint or --progressive"
+                    .to_string(),
+            );
+        }
+    }
+    let format = format
+        .or_else(|| output.as_deref().and_then(Format::from_path))
+        .unwrap_or(Format::Ppm);
+    Ok(Command::Render(Box::new(Options {
+        scene,
+        output,
+        format,
+        width,
+        height,
+        fov,
+        samples,
+        integrator,
+        seed,
+        time,
+        frames,
+        fps,
+        shutter,
+        aovs,
+        region,
+        full_frame,
+        checkpoint,
+        checkpoint_interval,
+        tile_size,
+        tile_order,
+        progressive,
+        time_budget,
+        sample_budget,
+        threads,
+        serve,
+        farm_timeout,
+        progress,
+        stats,
+    })))
+}
+
+// the image size is only known once the scene is read; a region reaching past it would
+// otherwise be cropped, or come out empty, without a word
+pub fn check_region(region: &Tile, width: usize, height: usize) -> Result<(), String> {
+    let fits = |start: usize, size: usize, limit: usize| {
+        start.checked_add(size).is_some_and(|end| end <= limit)
+    };
+    if fits(region.x, region.width, width) && fits(region.y, region.height, height) {
+        return Ok(());
+    }
+    Err(format!(
+        "--region {},{},{},{} does not fit in the {}x{} image",
+        region.x, region.y, region.width, region.height, width, height
+    ))
+}
+
+fn value<I: Iterator<Item = String>>(args: &mut I, option: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("{} expects a value", option))
+}
+
+fn positive(value: &str, option: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(v) if v > 0 => Ok(v),
+        _ => Err(format!(
+            "{} expects a positive integer, found '{}'",
+            option, value
+        )),
+    }
+}
+
+fn seconds(value: &str, option: &str) -> Result<Duration, String> {
+    match value.parse::<f64>().ok().map(Duration::try_from_secs_f64) {
+        Some(Ok(duration)) if !duration.is_zero() => Ok(duration),
+        _ => Err(format!(
+            "{} expects a positive number of seconds, found '{}'",
+            option, value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn defaults() {
+        let command = parse(args("scene.txt")).unwrap();
+        assert_eq!(
+            command,
+            Command::Render(Box::new(Options {
+                scene: PathBuf::from("scene.txt"),
+                output: None,
+                format: Format::Ppm,
+                width: None,
+                height: None,
+                fov: None,
+                samples: None,
+                integrator: None,
+                seed: None,
+                time: None,
+                frames: None,
+                fps: 24.0,
+                shutter: None,
+                aovs: vec![],
+                region: None,
+                full_frame: false,
+                checkpoint: None,
+                checkpoint_interval: None,
+                tile_size: None,
+                tile_order: None,
+                progressive: false,
+                time_budget: None,
+                sample_budget: None,
+                threads: None,
+                serve: None,
+                farm_timeout: None,
+                progress: true,
+                stats: true,
+            }))
+        );
+    }
+
+    #[test]
+    fn format_from_output_extension() {
+        match parse(args("scene.txt -o out.bmp -W 640 -j 2 --no-progress")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(options.format, Format::Bmp);
+                assert_eq!(options.output, Some(PathBuf::from("out.bmp")));
+                assert_eq!(options.width, Some(640));
+                assert_eq!(options.threads, Some(2));
+                assert!(!options.progress);
+            }
+            _ => panic!("expected render command"),
+        }
+    }
+
+    #[test]
+    fn integrator_and_seed() {
+        match parse(args("scene.txt -i path --seed 7")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(options.integrator, Some(IntegratorKind::Path));
+                assert_eq!(options.seed, Some(7));
+            }
+            _ => panic!("expected render command"),
+        }
+        assert_eq!(
+            parse(args("scene.txt --integrator photon")),
+            Err("unknown integrator 'photon' (expected whitted, path or ao)".to_string())
+        );
+        assert_eq!(
+            parse(args("scene.txt --seed -1")),
+            Err("--seed expects a non-negative integer, found '-1'".to_string())
+        );
+    }
+
+    #[test]
+    fn output_passes() {
+        match parse(args("scene.txt --aov depth=z.ppm --aov object-id=ids.bmp")).unwrap() {
+            Command::Render(options) => assert_eq!(
+                options.aovs,
+                vec![
+                    (Aov::Depth, PathBuf::from("z.ppm")),
+                    (Aov::ObjectId, PathBuf::from("ids.bmp"))
+                ]
+            ),
+            _ => panic!("expected render command"),
+        }
+        assert_eq!(
+            parse(args("scene.txt --aov depth")),
+            Err("--aov expects <pass>=<path>, found 'depth'".to_string())
+        );
+        assert_eq!(
+            parse(args("scene.txt --aov motion=m.ppm")),
+            Err("unknown output pass 'motion'".to_string())
+        );
+    }
+
+    #[test]
+    fn tiles_and_progressive() {
+        match parse(args(
+            "scene.txt --tile-size 16 --tile-order hilbert --progressive",
+        ))
+        .unwrap()
+        {
+            Command::Render(options) => {
+                assert_eq!(options.tile_size, Some(16));
+                assert_eq!(options.tile_order, Some(TileOrder::Hilbert));
+                assert!(options.progressive);
+            }
+            _ => panic!("expected render command"),
+        }
+        match parse(args("scene.txt --time-budget 1.5 --sample-budget 64")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(options.time_budget, Some(Duration::from_millis(1500)));
+                assert_eq!(options.sample_budget, Some(64));
+            }
+            _ => panic!("expected render command"),
+        }
+        assert_eq!(
+            parse(args("scene.txt --time-budget -2")),
+            Err("--time-budget expects a positive number of seconds, found '-2'".to_string())
+        );
+        assert_eq!(
+            parse(args("scene.txt --tile-order zigzag")),
+            Err("unknown tile order 'zigzag' (expected spiral, hilbert or scanline)".to_string())
+        );
+    }
+
+    #[test]
+    fn checkpoints() {
+        match parse(args("scene.txt --checkpoint run.ckpt --checkpoint-every 5")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(options.checkpoint, Some(PathBuf::from("run.ckpt")));
+                assert_eq!(options.checkpoint_interval, Some(Duration::from_secs(5)));
+            }
+            _ => panic!("expected render command"),
+        }
+        assert_eq!(
+            parse(args("scene.txt --checkpoint-every 0")),
+            Err("--checkpoint-every expects a positive number of seconds, found '0'".to_string())
+        );
+    }
+
+    #[test]
+    fn workers_and_coordinators() {
+        assert_eq!(
+            parse(args("--worker 10.0.0.1:7878 -j 4")),
+            Ok(Command::Worker {
+                address: "10.0.0.1:7878".to_string(),
+                threads: Some(4),
+                stats: true,
+            })
+        );
+        assert_eq!(
+            parse(args("scene.txt --worker 10.0.0.1:7878")),
+            Err("a worker gets its scene from the coordinator".to_string())
+        );
+        match parse(args("scene.txt --serve 0.0.0.0:7878")).unwrap() {
+            Command::Render(options) => assert_eq!(options.serve, Some("0.0.0.0:7878".to_string())),
+            _ => panic!("expected render command"),
+        }
+        assert!(parse(args("scene.txt --serve 0.0.0.0:7878 --progressive")).is_err());
+        match parse(args("scene.txt --serve 0.0.0.0:7878 --farm-timeout 30")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(options.farm_timeout, Some(Duration::from_secs(30)))
+            }
+            _ => panic!("expected render command"),
+        }
+        assert_eq!(
+            parse(args("scene.txt --farm-timeout 30")),
+            Err("--farm-timeout needs --serve".to_string())
+        );
+    }
+
+    #[test]
+    fn animation_times() {
+        match parse(args("scene.txt --frames 0..2.5 --fps 12 -o out/f-###.ppm")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(options.frames, Some((0.0, 2.5)));
+                assert_eq!(options.fps, 12.0);
+            }
+            _ => panic!("expected render command"),
+        }
+        match parse(args("scene.txt --time 1.5 --shutter 0.02")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(options.time, Some(1.5));
+                assert_eq!(options.shutter, Some(0.02));
+            }
+            _ => panic!("expected render command"),
+        }
+        assert_eq!(
+            parse(args("scene.txt --frames 2..1 -o f.ppm")),
+            Err("--frames expects <start>..<end> in seconds, found '2..1'".to_string())
+        );
+        assert_eq!(
+            parse(args("scene.txt --frames 0..1")),
+            Err("--frames needs an output file to number".to_string())
+        );
+    }
+
+    #[test]
+    fn regions() {
+        match parse(args("scene.txt --region 10,20,30,40 --full-frame")).unwrap() {
+            Command::Render(options) => {
+                assert_eq!(
+                    options.region,
+                    Some(Tile {
+                        x: 10,
+                        y: 20,
+                        width: 30,
+                        height: 40
+                    })
+                );
+                assert!(options.full_frame);
+            }
+            _ => panic!("expected render command"),
+        }
+        for bad in ["1,2,3", "1,2,0,4", "a,b,c,d"] {
+            assert_eq!(
+                parse(args(&format!("scene.txt --region {}", bad))),
+                Err(format!("--region expects X,Y,W,H, found '{}'", bad))
+            );
+        }
+        let region = |x, y, width, height| Tile {
+            x,
+            y,
+            width,
+            height,
+        };
+        assert_eq!(check_region(&region(0, 0, 400, 300), 400, 300), Ok(()));
+        assert_eq!(check_region(&region(390, 10, 10, 10), 400, 300), Ok(()));
+        assert_eq!(
+            check_region(&region(5000, 0, 10, 10), 400, 300),
+            Err("--region 5000,0,10,10 does not fit in the 400x300 image".to_string())
+        );
+        assert!(check_region(&region(395, 0, 10, 10), 400, 300).is_err());
+        assert!(check_region(&region(0, usize::MAX, 1, 1), 400, 300).is_err());
+    }
+
+    #[test]
+    fn explicit_format_wins() {
+        match parse(args("scene.txt -o out.bmp --format ppm")).unwrap() {
+            Command::Render(options) => assert_eq!(options.format, Format::Ppm),
+            _ => panic!("expected render command"),
+        }
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(parse(args("")), Err("no scene file given".to_string()));
+        assert_eq!(
+            parse(args("scene.txt -s 0")),
+            Err("-s expects a positive integer, found '0'".to_string())
+        );
+        assert_eq!(
+            parse(args("scene.txt --width")),
+            Err("--width expects a value".to_string())
+        );
+        assert_eq!(
+            parse(args("scene.txt --bogus")),
+            Err("unknown option '--bogus'".to_string())
+        );
+        assert_eq!(
+            parse(args("scene.txt -f png")),
+            Err("unsupported format 'png' (expected ppm or bmp)".to_string())
+        );
+    }
+}
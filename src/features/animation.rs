@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+
+use super::{
+    camera::Camera,
+    color::Color,
+    error::{Error, Result},
+    matrice::Matrice,
+    point::Point,
+    transformations::{
+        rotation_x, rotation_y, rotation_z, scaling, translation, view_transformation,
+    },
+    tuple::Tuple,
+    vector::Vector,
+    world::World,
+};
+
+// how a track moves from one key to the next
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    // a Catmull-Rom spline through the keys, easing through them instead of turning
+    // sharply at each one
+    Spline,
+}
+
+// values a track can blend between
+pub trait Keyable: Copy {
+    fn to_tuple(self) -> Tuple;
+    fn from_tuple(tuple: Tuple) -> Self;
+}
+
+impl Keyable for f64 {
+    fn to_tuple(self) -> Tuple {
+        Tuple::new(self, 0.0, 0.0)
+    }
+    fn from_tuple(tuple: Tuple) -> Self {
+        tuple.x
+    }
+}
+
+impl Keyable for Point {
+    fn to_tuple(self) -> Tuple {
+        self.position
+    }
+    fn from_tuple(tuple: Tuple) -> Self {
+        Point::from(tuple)
+    }
+}
+
+impl Keyable for Vector {
+    fn to_tuple(self) -> Tuple {
+        self.position
+    }
+    fn from_tuple(tuple: Tuple) -> Self {
+        Vector::from(tuple)
+    }
+}
+
+impl Keyable for Color {
+    fn to_tuple(self) -> Tuple {
+        self.rgb
+    }
+    fn from_tuple(tuple: Tuple) -> Self {
+        Color { rgb: tuple }
+    }
+}
+
+// a value keyed at points in time; it holds still before the first key and after
+// the last
+#[derive(Debug, PartialEq, Clone)]
+pub struct Track<T> {
+    keys: Vec<(f64, T)>,
+    pub interpolation: Interpolation,
+}
+
+impl<T: Keyable> Track<T> {
+    pub fn new(interpolation: Interpolation) -> Self {
+        Track {
+            keys: vec![],
+            interpolation,
+        }
+    }
+
+    pub fn constant(value: T) -> Self {
+        let mut track = Track::new(Interpolation::Linear);
+        track.insert(0.0, value);
+        track
+    }
+
+    // adds a key, replacing any key already at that time
+    pub fn insert(&mut self, time: f64, value: T) {
+        match self.keys.binary_search_by(|(t, _)| t.total_cmp(&time)) {
+            Ok(ix) => self.keys[ix].1 = value,
+            Err(ix) => self.keys.insert(ix, (time, value)),
+        }
+    }
+
+    pub fn get_keys(&self) -> &[(f64, T)] {
+        &self.keys
+    }
+
+    pub fn at(&self, time: f64) -> Option<T> {
+        let (first, last) = (self.keys.first()?, self.keys.last()?);
+        if time <= first.0 {
+            return Some(first.1);
+        }
+        if time >= last.0 {
+            return Some(last.1);
+        }
+        let next = self.keys.partition_point(|(t, _)| *t <= time);
+        let (t0, t1) = (self.keys[next - 1].0, self.keys[next].0);
+        let u = (time - t0) / (t1 - t0);
+        let key = |ix: usize| self.keys[ix.min(self.keys.len() - 1)].1.to_tuple();
+        let (p1, p2) = (key(next - 1), key(next));
+        let blended = match self.interpolation {
+            Interpolation::Linear => p1 * (1.0 - u) + p2 * u,
+            Interpolation::Spline => {
+                // the end keys stand in for the missing neighbours at either end
+                let (p0, p3) = (key(next.saturating_sub(2)), key(next + 1));
+                let (u2, u3) = (u * u, u * u * u);
+                p0 * (0.5 * (-u + 2.0 * u2 - u3))
+                    + p1 * (0.5 * (2.0 - 5.0 * u2 + 3.0 * u3))
+                    + p2 * (0.5 * (u + 4.0 * u2 - 3.0 * u3))
+                    + p3 * (0.5 * (u3 - u2))
+            }
+        };
+        Some(T::from_tuple(blended))
+    }
+}
+
+// what moves on one shape. Translation, rotation (in radians about x, then y, then z)
+// and scale are applied in that order on top of the shape's own transform
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShapeTracks {
+    pub base: Matrice,
+    pub translate: Option<Track<Vector>>,
+    pub rotate: Option<Track<Vector>>,
+    pub scale: Option<Track<Vector>>,
+    pub color: Option<Track<Color>>,
+    pub ambient: Option<Track<f64>>,
+    pub diffuse: Option<Track<f64>>,
+    pub specular: Option<Track<f64>>,
+    pub shininess: Option<Track<f64>>,
+}
+
+impl ShapeTracks {
+    pub fn new(base: Matrice) -> Self {
+        ShapeTracks {
+            base,
+            translate: None,
+            rotate: None,
+            scale: None,
+            color: None,
+            ambient: None,
+            diffuse: None,
+            specular: None,
+            shininess: None,
+        }
+    }
+
+    pub fn transform_at(&self, time: f64) -> Matrice {
+        let mut transform = self.base.clone();
+        if let Some(s) = self.scale.as_ref().and_then(|track| track.at(time)) {
+            let s = s.position;
+            transform = scaling(s.x, s.y, s.z) * transform;
+        }
+        if let Some(r) = self.rotate.as_ref().and_then(|track| track.at(time)) {
+            let r = r.position;
+            transform = rotation_z(r.z) * rotation_y(r.y) * rotation_x(r.x) * transform;
+        }
+        if let Some(t) = self.translate.as_ref().and_then(|track| track.at(time)) {
+            let t = t.position;
+            transform = translation(t.x, t.y, t.z) * transform;
+        }
+        transform
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LightTracks {
+    pub position: Option<Track<Point>>,
+    pub intensity: Option<Track<Color>>,
+}
+
+// where the camera is and what it looks at over time
+#[derive(Debug, PartialEq, Clone)]
+pub struct CameraPath {
+    pub from: Track<Point>,
+    pub to: Track<Point>,
+    pub up: Track<Vector>,
+}
+
+impl CameraPath {
+    pub fn transform_at(&self, time: f64) -> Option<Matrice> {
+        Some(view_transformation(
+            self.from.at(time)?,
+            self.to.at(time)?,
+            self.up.at(time)?,
+        ))
+    }
+}
+
+// everything in a scene that changes over time
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Animation {
+    // by shape id
+    pub shapes: HashMap<String, ShapeTracks>,
+    pub light: LightTracks,
+    pub camera: Option<CameraPath>,
+}
+
+impl Animation {
+    // sets the world and camera up as they are at a time
+    pub fn apply(&self, world: &mut World, camera: &mut Camera, time: f64) -> Result<()> {
+        for (id, tracks) in &self.shapes {
+            let shape = world
+                .objects
+                .get_mut(id)
+                .ok_or_else(|| Error::UnknownShape(id.clone()))?;
+            shape.set_transform(tracks.transform_at(time))?;
+            let mut material = shape.get_material();
+            let before = material.clone();
+            // splines overshoot their keys, which must not take a material out of range
+            if let Some(color) = tracks.color.as_ref().and_then(|t| t.at(time)) {
+                let rgb = color.rgb;
+                material.color = Color::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0));
+            }
+            for (value, track, least) in [
+                (&mut material.ambient, &tracks.ambient, 0.0),
+                (&mut material.diffuse, &tracks.diffuse, 0.0),
+                (&mut material.specular, &tracks.specular, 0.0),
+                (
+                    &mut material.shininess,
+                    &tracks.shininess,
+                    f64::MIN_POSITIVE,
+                ),
+            ] {
+                if let Some(v) = track.as_ref().and_then(|t| t.at(time)) {
+                    *value = v.max(least);
+                }
+            }
+            if material != before {
+                shape.set_material(material)?;
+            }
+        }
+        if let Some(position) = self.light.position.as_ref().and_then(|t| t.at(time)) {
+            world.light.position = position;
+        }
+        if let Some(intensity) = self.light.intensity.as_ref().and_then(|t| t.at(time)) {
+            world.light.intensity = intensity;
+        }
+        if let Some(transform) = self.camera.as_ref().and_then(|c| c.transform_at(time)) {
+            camera.set_transform(transform)?;
+        }
+        Ok(())
+    }
+
+    // poses the scene as the shutter opens and has animated shapes move on to where they
+    // stand as it closes, so samples spread over the shutter blur them
+    pub fn apply_shutter(
+        &self,
+        world: &mut World,
+        camera: &mut Camera,
+        open: f64,
+        close: f64,
+    ) -> Result<()> {
+        self.apply(world, camera, open)?;
+        for (id, tracks) in &self.shapes {
+            let shape = world
+                .objects
+                .get_mut(id)
+                .ok_or_else(|| Error::UnknownShape(id.clone()))?;
+            let end = tracks.transform_at(close);
+            let moves = end != shape.get_transform();
+            shape.set_motion(moves.then_some(end))?;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty() && self.light == LightTracks::default() && self.camera.is_none()
+    }
+}
+
+// the times of the frames from start to end, both included, at a frame rate
+pub fn frame_times(start: f64, end: f64, fps: f64) -> Vec<f64> {
+    if end < start || fps <= 0.0 {
+        return vec![];
+    }
+    // a little slack keeps an end that is a whole number of frames away
+    let frames = ((end - start) * fps + 1e-9).floor() as usize + 1;
+    (0..frames).map(|i| start + i as f64 / fps).collect()
+}
+
+#[cfg(test)]
+mod animation_tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::features::{light::Light, shape::sphere::Sphere, shape::Shape};
+
+    fn track(interpolation: Interpolation, keys: &[(f64, f64)]) -> Track<f64> {
+        let mut track = Track::new(interpolation);
+        for (time, value) in keys {
+            track.insert(*time, *value);
+        }
+        track
+    }
+
+    #[test]
+    fn linear_tracks_hold_outside_their_keys() {
+        let t = track(
+            Interpolation::Linear,
+            &[(2.0, 10.0), (0.0, 0.0), (3.0, 0.0)],
+        );
+        assert_eq!(t.get_keys()[0], (0.0, 0.0));
+        assert_eq!(t.at(-1.0), Some(0.0));
+        assert_eq!(t.at(1.0), Some(5.0));
+        assert_eq!(t.at(2.5), Some(5.0));
+        assert_eq!(t.at(9.0), Some(0.0));
+        assert_eq!(Track::<f64>::new(Interpolation::Linear).at(0.0), None);
+    }
+
+    #[test]
+    fn splines_pass_through_their_keys_smoothly() {
+        let keys = [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0), (3.0, 1.0)];
+        let spline = track(Interpolation::Spline, &keys);
+        for (time, value) in keys {
+            assert!((spline.at(time).unwrap() - value).abs() < 1e-12);
+        }
+        // the curve levels off over the peak key where a straight line turns sharply
+        assert!(spline.at(0.9).unwrap() > 0.95);
+        assert!(spline.at(1.1).unwrap() > 0.95);
+        assert!(track(Interpolation::Linear, &keys).at(1.1).unwrap() < 0.95);
+        // evenly spaced keys on a line stay on it
+        let line = track(Interpolation::Spline, &[(0.0, 0.0), (1.0, 2.0), (2.0, 4.0)]);
+        assert!((line.at(0.5).unwrap() - 1.0).abs() < 0.2);
+        assert!((line.at(1.5).unwrap() - 3.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn frame_times_include_both_ends() {
+        assert_eq!(frame_times(0.0, 1.0, 4.0), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(
+            frame_times(1.0, 1.1, 24.0),
+            [1.0, 1.0 + 1.0 / 24.0, 1.0 + 2.0 / 24.0]
+        );
+        assert!(frame_times(1.0, 0.0, 24.0).is_empty());
+    }
+
+    #[test]
+    fn animations_pose_shapes_lights_and_cameras() {
+        let mut world = World::new(Light::new(
+            Point::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut sphere = Sphere::default();
+        sphere.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let id = sphere.get_shape_id();
+        world.add_shape(Box::new(sphere));
+        let mut camera = Camera::new(10.0, 10.0, PI / 2.0);
+
+        let mut tracks = ShapeTracks::new(scaling(2.0, 2.0, 2.0));
+        let mut translate = Track::new(Interpolation::Linear);
+        translate.insert(0.0, Vector::new(0.0, 0.0, 0.0));
+        translate.insert(2.0, Vector::new(4.0, 0.0, 0.0));
+        tracks.translate = Some(translate);
+        let mut rotate = Track::new(Interpolation::Linear);
+        rotate.insert(0.0, Vector::new(0.0, PI / 2.0, 0.0));
+        tracks.rotate = Some(rotate);
+        let mut diffuse = Track::new(Interpolation::Linear);
+        diffuse.insert(0.0, 0.2);
+        diffuse.insert(2.0, 0.6);
+        tracks.diffuse = Some(diffuse);
+        let mut animation = Animation::default();
+        animation.shapes.insert(id.clone(), tracks);
+        let mut position = Track::new(Interpolation::Linear);
+        position.insert(0.0, Point::new(0.0, 10.0, 0.0));
+        position.insert(2.0, Point::new(0.0, 20.0, 0.0));
+        animation.light.position = Some(position);
+        animation.camera = Some(CameraPath {
+            from: Track::constant(Point::new(0.0, 0.0, -5.0)),
+            to: Track::constant(Point::new(0.0, 0.0, 0.0)),
+            up: Track::constant(Vector::new(0.0, 1.0, 0.0)),
+        });
+        assert!(!animation.is_empty());
+
+        animation.apply(&mut world, &mut camera, 1.0).unwrap();
+        let shape = world.get_shape(&id).unwrap();
+        // scaled by the scene, then turned, then moved
+        assert_eq!(
+            shape.get_transform(),
+            translation(2.0, 0.0, 0.0) * rotation_y(PI / 2.0) * scaling(2.0, 2.0, 2.0)
+        );
+        assert!((shape.get_material().diffuse - 0.4).abs() < 1e-12);
+        assert_eq!(world.light.position, Point::new(0.0, 15.0, 0.0));
+        assert_eq!(
+            camera.get_transform(),
+            view_transformation(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0)
+            )
+        );
+    }
+
+    #[test]
+    fn splines_keep_materials_in_range() {
+        let mut world = World::new(Light::new(
+            Point::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let sphere = Sphere::default();
+        let id = sphere.get_shape_id();
+        world.add_shape(Box::new(sphere));
+        let mut camera = Camera::new(10.0, 10.0, PI / 2.0);
+        let mut tracks = ShapeTracks::new(Matrice::identity_matrix(4));
+        let keys = [(0.0, 0.0), (1.0, 0.0), (2.0, 1.0)];
+        let diffuse = track(Interpolation::Spline, &keys);
+        assert!(diffuse.at(0.5).unwrap() < 0.0);
+        tracks.diffuse = Some(diffuse);
+        let keys = [(0.0, 1.0), (1.0, 1.0), (2.0, 200.0)];
+        tracks.shininess = Some(track(Interpolation::Spline, &keys));
+        let mut color = Track::new(Interpolation::Spline);
+        color.insert(0.0, Color::new(0.0, 0.0, 0.0));
+        color.insert(1.0, Color::new(0.0, 0.0, 0.0));
+        color.insert(2.0, Color::new(1.0, 1.0, 1.0));
+        tracks.color = Some(color);
+        let mut animation = Animation::default();
+        animation.shapes.insert(id.clone(), tracks);
+
+        animation.apply(&mut world, &mut camera, 0.5).unwrap();
+        let material = world.get_shape(&id).unwrap().get_material();
+        assert_eq!(material.diffuse, 0.0);
+        assert_eq!(material.color, Color::new(0.0, 0.0, 0.0));
+        assert!(material.shininess > 0.0);
+    }
+
+    #[test]
+    fn shutters_set_animated_shapes_moving() {
+        let mut world = World::new(Light::new(
+            Point::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let (sliding, parked) = (Sphere::default(), Sphere::default());
+        let (sliding_id, parked_id) = (sliding.get_shape_id(), parked.get_shape_id());
+        world.add_shapes(vec![Box::new(sliding), Box::new(parked)]);
+        let mut camera = Camera::new(10.0, 10.0, PI / 2.0);
+
+        let mut animation = Animation::default();
+        let mut tracks = ShapeTracks::new(Matrice::identity_matrix(4));
+        let mut translate = Track::new(Interpolation::Linear);
+        translate.insert(0.0, Vector::new(0.0, 0.0, 0.0));
+        translate.insert(1.0, Vector::new(4.0, 0.0, 0.0));
+        tracks.translate = Some(translate);
+        animation.shapes.insert(sliding_id.clone(), tracks);
+        let mut tracks = ShapeTracks::new(Matrice::identity_matrix(4));
+        tracks.translate = Some(Track::constant(Vector::new(1.0, 0.0, 0.0)));
+        animation.shapes.insert(parked_id.clone(), tracks);
+
+        animation
+            .apply_shutter(&mut world, &mut camera, 0.5, 0.75)
+            .unwrap();
+        let sliding = world.get_shape(&sliding_id).unwrap();
+        assert_eq!(sliding.get_transform(), translation(2.0, 0.0, 0.0));
+        let motion = sliding.get_motion().unwrap();
+        assert_eq!(motion.get_end(), &translation(3.0, 0.0, 0.0));
+        assert!(world.get_shape(&parked_id).unwrap().get_motion().is_none());
+        assert!(world.is_moving());
+    }
+}
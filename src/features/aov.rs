@@ -0,0 +1,168 @@
+use super::{
+    canvas::Canvas,
+    color::Color,
+    computation::Computation,
+    consts::{BLACK, WHITE},
+    control::Progress,
+    error::{Error, Result},
+    intersection::hit,
+    ray::Ray,
+    shape::Shape,
+    stats::Stats,
+    world::World,
+};
+
+// arbitrary output variables: extra images rendered alongside the beauty pass,
+// each read from what the primary ray through the pixel centre hits
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Aov {
+    // distance along the ray to the hit, zero where nothing is hit
+    Depth,
+    // world space shading normal, mapped from -1..1 to 0..1
+    Normal,
+    // the share of light the surface keeps, before any lighting
+    Albedo,
+    // white where the main light is blocked
+    Shadow,
+    // a flat false color per object
+    ObjectId,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 5] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::Shadow,
+        Aov::ObjectId,
+    ];
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Aov::ALL
+            .into_iter()
+            .find(|aov| aov.name() == name)
+            .ok_or_else(|| Error::parse(format!("unknown output pass '{}'", name)))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::Shadow => "shadow",
+            Aov::ObjectId => "object-id",
+        }
+    }
+}
+
+// the beauty image and one image per requested output variable, in request order
+#[derive(Debug, PartialEq)]
+pub struct Frame {
+    pub beauty: Canvas,
+    pub passes: Vec<(Aov, Canvas)>,
+    pub progress: Progress,
+    pub stats: Stats,
+}
+
+impl Frame {
+    pub fn pass(&self, aov: Aov) -> Option<&Canvas> {
+        self.passes
+            .iter()
+            .find(|(a, _)| *a == aov)
+            .map(|(_, canvas)| canvas)
+    }
+}
+
+// every requested variable for one ray, sharing a single intersection test
+pub fn evaluate(aovs: &[Aov], world: &World, ray: &Ray) -> Result<Vec<Color>> {
+    if aovs.is_empty() {
+        return Ok(vec![]);
+    }
+    let hit = match world.intersect(ray).and_then(hit) {
+        Some(hit) => hit,
+        None => return Ok(vec![BLACK; aovs.len()]),
+    };
+    let shape = &world.get_shape_at(&hit.shape_id, ray.time)?;
+    let comps = Computation::new(ray, &hit, shape);
+    aovs.iter()
+        .map(|aov| {
+            Ok(match aov {
+                Aov::Depth => {
+                    let t = hit.t * ray.direction.magnitude();
+                    Color::new(t, t, t)
+                }
+                Aov::Normal => {
+                    let n = comps.normalv.position;
+                    Color::new(n.x, n.y, n.z) * 0.5 + WHITE * 0.5
+                }
+                Aov::Albedo => shape.get_material().albedo(shape, &comps.point),
+                Aov::Shadow if world.is_shadowed(&comps.over_point, comps.time) => WHITE,
+                Aov::Shadow => BLACK,
+                Aov::ObjectId => {
+                    let index = world.keys.iter().position(|k| *k == hit.shape_id);
+                    false_color(index.unwrap_or_default())
+                }
+            })
+        })
+        .collect()
+}
+
+// hues a golden angle apart so neighbouring indices never look alike
+fn false_color(index: usize) -> Color {
+    let hue = (index as f64 * 0.618_033_988_75).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    match hue as usize {
+        0 => Color::new(1.0, x, 0.0),
+        1 => Color::new(x, 1.0, 0.0),
+        2 => Color::new(0.0, 1.0, x),
+        3 => Color::new(0.0, x, 1.0),
+        4 => Color::new(x, 0.0, 1.0),
+        _ => Color::new(1.0, 0.0, x),
+    }
+}
+
+#[cfg(test)]
+mod aov_tests {
+    use super::*;
+    use crate::features::{point::Point, vector::Vector};
+
+    #[test]
+    fn passes_read_the_primary_hit() {
+        let world = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let colors = evaluate(&Aov::ALL, &world, &ray).unwrap();
+        assert_eq!(colors[0], Color::new(4.0, 4.0, 4.0));
+        assert_eq!(colors[1], Color::new(0.5, 0.5, 0.0));
+        assert_eq!(colors[2], Color::new(0.56, 0.7, 0.42));
+        assert_eq!(colors[3], BLACK);
+        assert_eq!(colors[4], false_color(0));
+        let behind = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(evaluate(&[Aov::Shadow], &world, &behind).unwrap()[0], WHITE);
+    }
+
+    #[test]
+    fn misses_are_black_in_every_pass() {
+        let world = World::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(
+            evaluate(&Aov::ALL, &world, &ray).unwrap(),
+            vec![BLACK; Aov::ALL.len()]
+        );
+    }
+
+    #[test]
+    fn objects_get_distinct_colors() {
+        let colors: Vec<Color> = (0..8).map(false_color).collect();
+        for (i, a) in colors.iter().enumerate() {
+            assert!(colors[i + 1..].iter().all(|b| a != b));
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for aov in Aov::ALL {
+            assert_eq!(Aov::parse(aov.name()).unwrap(), aov);
+        }
+        assert!(Aov::parse("motion").is_err());
+    }
+}
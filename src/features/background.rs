@@ -0,0 +1,153 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{canvas::Canvas, color::Color, consts::BLACK, vector::Vector};
+
+// what a ray sees when it leaves the scene without hitting anything
+#[derive(Debug, PartialEq, Clone)]
+pub enum Background {
+    Solid(Color),
+    // blends from the horizon color straight ahead to the zenith color overhead
+    Sky { horizon: Color, zenith: Color },
+    Environment(Environment),
+}
+
+impl Background {
+    pub fn at(&self, direction: &Vector) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Sky { horizon, zenith } => {
+                let up = direction.normalize().position.y.max(0.0);
+                *horizon + (*zenith - *horizon) * up
+            }
+            Background::Environment(environment) => environment.at(direction),
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(BLACK)
+    }
+}
+
+// an equirectangular (latitude/longitude) image wrapped around the scene
+#[derive(Debug, PartialEq, Clone)]
+pub struct Environment {
+    image: Arc<Canvas>,
+    // turns the image about the vertical axis, in radians
+    rotation: f64,
+    strength: f64,
+}
+
+impl Environment {
+    pub fn new(image: Arc<Canvas>, rotation: f64, strength: f64) -> Self {
+        Environment {
+            image,
+            rotation,
+            strength,
+        }
+    }
+
+    pub fn at(&self, direction: &Vector) -> Color {
+        let (width, height) = (self.image.width, self.image.height);
+        if width == 0 || height == 0 {
+            return BLACK;
+        }
+        let d = direction.normalize().position;
+        // -z, straight ahead of the default camera, is the middle of the image
+        let u = (0.5 + (d.x.atan2(-d.z) - self.rotation) / (2.0 * PI)).rem_euclid(1.0);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+        // texel centres sit half a texel in; longitude wraps, latitude clamps
+        let x = u * width as f64 - 0.5;
+        let y = (v * height as f64 - 0.5).clamp(0.0, (height - 1) as f64);
+        let (x0, y0) = (x.floor(), y.floor() as usize);
+        let (fx, fy) = (x - x0, y - y0 as f64);
+        let y1 = (y0 + 1).min(height - 1);
+        let column = |x: f64| (x as i64).rem_euclid(width as i64) as usize;
+        let (x0, x1) = (column(x0), column(x0 + 1.0));
+        let pixel = |x: usize, y: usize| self.image.canvas[y][x];
+        let top = pixel(x0, y0) * (1.0 - fx) + pixel(x1, y0) * fx;
+        let bottom = pixel(x0, y1) * (1.0 - fx) + pixel(x1, y1) * fx;
+        (top * (1.0 - fy) + bottom * fy) * self.strength
+    }
+}
+
+#[cfg(test)]
+mod background_tests {
+    use super::*;
+
+    #[test]
+    fn sky_blends_up_to_the_zenith() {
+        let sky = Background::Sky {
+            horizon: Color::new(1.0, 1.0, 1.0),
+            zenith: Color::new(0.0, 0.0, 1.0),
+        };
+        assert_eq!(
+            sky.at(&Vector::new(0.0, 0.0, -1.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            sky.at(&Vector::new(0.0, 2.0, 0.0)),
+            Color::new(0.0, 0.0, 1.0)
+        );
+        let up = 0.5_f64.sqrt();
+        assert_eq!(
+            sky.at(&Vector::new(0.0, 1.0, -1.0)),
+            Color::new(1.0 - up, 1.0 - up, 1.0)
+        );
+        assert_eq!(
+            sky.at(&Vector::new(0.0, -1.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    fn quadrants() -> Arc<Canvas> {
+        // four columns of longitude, the bottom row half as bright as the top
+        let mut image = Canvas::new(4, 2);
+        for (x, color) in [
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 0.0),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            image.canvas[0][x] = color;
+            image.canvas[1][x] = color * 0.5;
+        }
+        Arc::new(image)
+    }
+
+    // the direction at a longitude measured from straight ahead, and a height
+    fn towards(longitude: f64, y: f64) -> Vector {
+        let flat = (1.0 - y * y).sqrt();
+        Vector::new(longitude.sin() * flat, y, -longitude.cos() * flat)
+    }
+
+    #[test]
+    fn environment_maps_directions_to_longitude_and_latitude() {
+        let env = Environment::new(quadrants(), 0.0, 1.0);
+        let row = 0.5_f64.sqrt();
+        // texel centres are a quarter turn apart either side of straight ahead
+        assert_eq!(env.at(&towards(-0.75 * PI, row)), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(env.at(&towards(-0.25 * PI, row)), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(env.at(&towards(0.25 * PI, row)), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(env.at(&towards(0.25 * PI, -row)), Color::new(0.0, 0.0, 0.5));
+        // straight ahead lies between two columns
+        assert_eq!(env.at(&towards(0.0, row)), Color::new(0.0, 0.5, 0.5));
+    }
+
+    #[test]
+    fn environment_wraps_and_rotates() {
+        let env = Environment::new(quadrants(), 0.0, 2.0);
+        let row = 0.5_f64.sqrt();
+        // directly behind sits on the seam between the last and first columns
+        assert_eq!(env.at(&towards(PI, row)), Color::new(2.0, 1.0, 0.0));
+        let turned = Environment::new(quadrants(), PI / 2.0, 1.0);
+        assert_eq!(
+            turned.at(&towards(0.25 * PI, row)),
+            Color::new(0.0, 1.0, 0.0)
+        );
+    }
+}
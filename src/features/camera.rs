@@ -1,8 +1,22 @@
 use super::{
-    canvas::Canvas, color::Color, consts::BLACK, matrice::Matrice, point::Point, ray::Ray,
+    aov::{self, Aov, Frame},
+    canvas::Canvas,
+    checkpoint::{fnv1a, Accumulator},
+    color::Color,
+    consts::BLACK,
+    control::RenderControl,
+    error::Result,
+    integrator::{sample_rng, Integrator},
+    matrice::Matrice,
+    point::Point,
+    progress::{ProgressObserver, Silent},
+    ray::Ray,
+    stats,
+    tiles::{tiles, RenderEvent, Tile, TileOrder},
     world::World,
 };
-use indicatif::ProgressBar;
+use rand::Rng;
+use std::{sync::Mutex, time::Instant};
 extern crate rayon;
 use rayon::prelude::*;
 
@@ -10,10 +24,22 @@ pub struct Camera {
     pub hsize: f64,
     pub vsize: f64,
     pub field_of_view: f64,
-    pub transform: Matrice,
+    transform: Matrice,
+    inverse: Matrice,
     pub pixel_size: f64,
     pub half_width: f64,
     pub half_height: f64,
+    pub samples: usize,
+    // everything sampled in a render is drawn from this, so equal seeds give equal images
+    pub seed: u64,
+    pub progress: Box<dyn ProgressObserver>,
+    pub integrator: Integrator,
+    pub tile_size: usize,
+    pub tile_order: TileOrder,
+    // refine the whole image a sample at a time instead of finishing pixels in one go
+    pub progressive: bool,
+    // only pixels inside are traced, rays still aim as they would in the full image
+    pub region: Option<Tile>,
 }
 
 impl Camera {
@@ -35,36 +61,292 @@ impl Camera {
             vsize,
             field_of_view,
             transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
             pixel_size: (half_width * 2.0) / hsize,
             half_width,
             half_height,
+            samples: 1,
+            seed: 0,
+            progress: Box::new(Silent),
+            integrator: Integrator::Whitted,
+            tile_size: 32,
+            tile_order: TileOrder::default(),
+            progressive: false,
+            region: None,
         }
     }
+    pub fn set_transform(&mut self, transform: Matrice) -> Result<()> {
+        self.inverse = transform.inverse()?;
+        self.transform = transform;
+        Ok(())
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
     pub fn ray_for_pixel(&self, px: f64, py: f64) -> Ray {
-        let xoffset = (px + 0.5) * self.pixel_size;
-        let yoffset = (py + 0.5) * self.pixel_size;
+        self.ray_for_sample(px, py, 0.5, 0.5)
+    }
+    pub fn ray_for_sample(&self, px: f64, py: f64, dx: f64, dy: f64) -> Ray {
+        let xoffset = (px + dx) * self.pixel_size;
+        let yoffset = (py + dy) * self.pixel_size;
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
-        let pixel = self.transform.inverse().unwrap() * Point::new(world_x, world_y, -1.0);
-        let origin = self.transform.inverse().unwrap() * Point::new(0.0, 0.0, 0.0);
+        let pixel = self.inverse.clone() * Point::new(world_x, world_y, -1.0);
+        let origin = self.inverse.clone() * Point::new(0.0, 0.0, 0.0);
         let direction = (pixel - origin).normalize();
         Ray::new(origin, direction)
     }
-    pub fn render(&self, world: &World) -> Canvas {
-        let mut image = Canvas::new(self.hsize as usize, self.vsize as usize);
-        let bar = ProgressBar::new((self.vsize * self.hsize) as u64);
-        image
-            .canvas
-            .par_iter_mut()
-            .enumerate()
-            .for_each(|(y, row)| {
-                row.par_iter_mut().enumerate().for_each(|(x, pixel)| {
-                    let ray = self.ray_for_pixel(x as f64, y as f64);
-                    bar.inc(1);
-                    *pixel = world.color_at(&ray);
-                })
-            });
-        image
+    pub fn render(&self, world: &World) -> Result<Canvas> {
+        Ok(self.render_passes(world, &[])?.beauty)
+    }
+    // renders the region over its place in a full size image, leaving the rest alone
+    pub fn render_into(&self, world: &World, image: &mut Canvas) -> Result<()> {
+        let region = self.region();
+        image.paste(&self.render(world)?, region.x, region.y);
+        Ok(())
+    }
+    // the part of the image that gets rendered: the region clipped to the image, or
+    // the whole image
+    pub fn region(&self) -> Tile {
+        let (width, height) = (self.hsize as usize, self.vsize as usize);
+        match self.region {
+            Some(region) => {
+                let x = region.x.min(width);
+                let y = region.y.min(height);
+                Tile {
+                    x,
+                    y,
+                    width: region.width.min(width - x),
+                    height: region.height.min(height - y),
+                }
+            }
+            None => Tile {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+        }
+    }
+    // the region split into tiles, in full image coordinates and render order
+    pub fn tiles(&self) -> Vec<Tile> {
+        let region = self.region();
+        tiles(region.width, region.height, self.tile_size, self.tile_order)
+            .into_iter()
+            .map(|tile| Tile {
+                x: tile.x + region.x,
+                y: tile.y + region.y,
+                ..tile
+            })
+            .collect()
+    }
+    // renders the beauty image together with the requested output variables
+    pub fn render_passes(&self, world: &World, aovs: &[Aov]) -> Result<Frame> {
+        self.render_with(world, aovs, &RenderControl::default(), &|_| {})
+    }
+    // renders tile by tile, reporting every finished tile and, when progressive, every
+    // finished pass over the image; the callback runs on the render threads. Once the
+    // control says stop no new tiles are started and the frame holds what got done.
+    // With a region set the frame only covers the region
+    pub fn render_with(
+        &self,
+        world: &World,
+        aovs: &[Aov],
+        control: &RenderControl,
+        on_event: &(dyn Fn(RenderEvent) + Sync),
+    ) -> Result<Frame> {
+        let started = Instant::now();
+        let region = self.region();
+        let (width, height) = (region.width, region.height);
+        let tiles = self.tiles();
+        let samples = self.samples.max(1);
+        // progressive renders take one sample per pixel per pass
+        let (passes, per_pass) = match (self.progressive, control.sample_budget) {
+            (true, budget) => (budget.unwrap_or(samples).max(1), 1),
+            (false, budget) => (1, budget.map_or(samples, |b| b.clamp(1, samples))),
+        };
+        self.progress.start((width * height * passes) as u64);
+        let key = control
+            .checkpoint
+            .as_ref()
+            .map(|checkpoint| self.checkpoint_key(checkpoint.scene_hash, aovs, per_pass));
+        let resumed = match (&control.checkpoint, key) {
+            (Some(checkpoint), Some(key)) => {
+                Accumulator::load(checkpoint, key, width, height, aovs)?
+            }
+            _ => None,
+        };
+        let state = Mutex::new(resumed.unwrap_or_else(|| Accumulator::new(width, height, aovs)));
+        let saved = Mutex::new(Instant::now());
+        let setup = started.elapsed();
+        for pass in 0..passes {
+            let target = (pass + 1) * per_pass;
+            tiles
+                .iter()
+                .par_bridge()
+                .try_for_each(|tile| -> Result<()> {
+                    if let Some(reason) = control.should_stop(started) {
+                        let mut state = state.lock().unwrap();
+                        state.frame.progress.stopped.get_or_insert(reason);
+                        return Ok(());
+                    }
+                    // whatever this thread did before the tile belongs to someone else
+                    stats::take();
+                    // pixels a checkpoint already took this far are not traced again
+                    let todo: Vec<(usize, usize)> = {
+                        let state = state.lock().unwrap();
+                        tile.pixels()
+                            .filter(|(x, y)| state.counts[y - region.y][x - region.x] < target)
+                            .collect()
+                    };
+                    let mut sums = Vec::with_capacity(todo.len());
+                    let mut extras = vec![];
+                    for (x, y) in &todo {
+                        let (px, py) = (*x as f64, *y as f64);
+                        let first = pass * per_pass;
+                        sums.push(self.sample_sum(
+                            world,
+                            px,
+                            py,
+                            first,
+                            per_pass,
+                            passes * per_pass,
+                        )?);
+                        // output variables come from the pixel centre, so one pass is enough
+                        if pass == 0 {
+                            extras.push(aov::evaluate(aovs, world, &self.ray_for_pixel(px, py))?);
+                        }
+                    }
+                    let pixels: Vec<Color> = {
+                        let mut state = state.lock().unwrap();
+                        let Accumulator {
+                            total,
+                            counts,
+                            frame,
+                        } = &mut *state;
+                        frame.stats.counters += stats::take();
+                        for (i, (x, y)) in todo.iter().enumerate() {
+                            let (x, y) = (x - region.x, y - region.y);
+                            total[y][x] = total[y][x] + sums[i];
+                            counts[y][x] += per_pass;
+                            frame.beauty.canvas[y][x] = total[y][x] * (1.0 / counts[y][x] as f64);
+                            if let Some(extra) = extras.get(i) {
+                                for ((_, canvas), color) in frame.passes.iter_mut().zip(extra) {
+                                    canvas.canvas[y][x] = *color;
+                                }
+                            }
+                        }
+                        tile.pixels()
+                            .map(|(x, y)| state.frame.beauty.canvas[y - region.y][x - region.x])
+                            .collect()
+                    };
+                    // the samples are copied under the lock and written after it, and a
+                    // thread finding a save under way leaves it to that one
+                    if let (Some(checkpoint), Some(key)) = (&control.checkpoint, key) {
+                        if let Ok(mut saved) = saved.try_lock() {
+                            if saved.elapsed() >= checkpoint.interval {
+                                let snapshot = state.lock().unwrap().snapshot();
+                                snapshot.save(checkpoint, key)?;
+                                *saved = Instant::now();
+                            }
+                        }
+                    }
+                    self.progress.advance(pixels.len() as u64);
+                    on_event(RenderEvent::Tile {
+                        pass,
+                        tile,
+                        pixels: &pixels,
+                    });
+                    Ok(())
+                })?;
+            let mut state = state.lock().unwrap();
+            if state.frame.progress.stopped.is_some() {
+                break;
+            }
+            state.frame.progress.passes += 1;
+            if self.progressive {
+                on_event(RenderEvent::Pass {
+                    pass,
+                    passes,
+                    image: &state.frame.beauty,
+                });
+            }
+        }
+        self.progress.finish();
+        let mut state = state.into_inner().unwrap();
+        let traced = started.elapsed();
+        state.frame.stats.phases = vec![("setup", setup), ("trace", traced - setup)];
+        if let (Some(checkpoint), Some(key)) = (&control.checkpoint, key) {
+            state.save(checkpoint, key)?;
+            let save = started.elapsed() - traced;
+            state.frame.stats.phases.push(("save", save));
+        }
+        let Accumulator {
+            counts, mut frame, ..
+        } = state;
+        let counts = counts.iter().flatten();
+        frame.progress.pixels = counts.clone().filter(|n| **n > 0).count();
+        frame.progress.samples = counts.min().copied().unwrap_or_default();
+        Ok(frame)
+    }
+    // checkpoints resume only renders whose pixels would come out the same, which
+    // holds whatever the tile layout or the number of progressive passes
+    fn checkpoint_key(&self, scene_hash: u64, aovs: &[Aov], per_pass: usize) -> u64 {
+        let camera = format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+            self.hsize,
+            self.vsize,
+            self.field_of_view,
+            self.transform,
+            self.integrator,
+            self.seed,
+            self.region(),
+            self.progressive,
+            per_pass,
+            aovs
+        );
+        fnv1a(&[&scene_hash.to_le_bytes(), camera.as_bytes()].concat())
+    }
+    pub fn pixel_color(&self, world: &World, px: f64, py: f64) -> Result<Color> {
+        let samples = self.samples.max(1);
+        Ok(self.sample_sum(world, px, py, 0, samples, samples)? * (1.0 / samples as f64))
+    }
+    // the summed light of a run of samples through a pixel, numbered from first, out of
+    // total over the whole render; each sample draws from its own stream, so which
+    // thread takes it never shows
+    fn sample_sum(
+        &self,
+        world: &World,
+        px: f64,
+        py: f64,
+        first: usize,
+        samples: usize,
+        total: usize,
+    ) -> Result<Color> {
+        let (x, y) = (px as usize, py as usize);
+        // whitted renders of one sample in all trace the pixel centre, but a lone sample
+        // still needs its time when something moves
+        if self.integrator == Integrator::Whitted && total <= 1 && !world.is_moving() {
+            stats::count(|c| c.primary_rays += 1);
+            let mut rng = sample_rng(self.seed, x, y, first);
+            let color = world.color_at_with(&self.ray_for_pixel(px, py), &mut rng)?;
+            return Ok(color * samples as f64);
+        }
+        // samples spread over the shutter interval blur whatever moves; the time is
+        // drawn either way so every sample's stream stays the same when nothing does
+        let moving = world.is_moving();
+        let mut color = BLACK;
+        for sample in first..first + samples {
+            stats::count(|c| c.primary_rays += 1);
+            let mut rng = sample_rng(self.seed, x, y, sample);
+            let mut ray = self.ray_for_sample(px, py, rng.gen(), rng.gen());
+            let time: f64 = rng.gen();
+            if moving {
+                ray = ray.with_time(time);
+            }
+            color = color + self.integrator.radiance(world, &ray, &mut rng)?;
+        }
+        Ok(color)
     }
 }
 
@@ -82,7 +364,7 @@ mod camera_tests {
         assert_eq!(c.hsize, 160.0);
         assert_eq!(c.vsize, 120.0);
         assert_eq!(c.field_of_view, PI / 2.0);
-        assert_eq!(c.transform, Matrice::identity_matrix(4));
+        assert_eq!(c.get_transform(), Matrice::identity_matrix(4));
     }
     #[test]
     fn pixel_size_horizontal_canvas() {
@@ -94,6 +376,12 @@ mod camera_tests {
         let c = Camera::new(125.0, 200.0, PI / 2.0);
         assert_eq!(c.pixel_size, 0.009999999999999998)
     }
+    #[test]
+    fn singular_transform_is_rejected() {
+        let mut c = Camera::new(160.0, 120.0, PI / 2.0);
+        assert!(c.set_transform(Matrice::new(4)).is_err());
+        assert_eq!(c.get_transform(), Matrice::identity_matrix(4));
+    }
 }
 
 #[cfg(test)]
@@ -125,7 +413,8 @@ mod ray_for_pixel_tests {
     #[test]
     fn test_ray_camera_transformed() {
         let mut c = Camera::new(201.0, 101.0, PI / 2.0);
-        c.transform = rotation_y(PI / 4.0) * translation(0.0, -2.0, 5.0);
+        c.set_transform(rotation_y(PI / 4.0) * translation(0.0, -2.0, 5.0))
+            .unwrap();
         let r = c.ray_for_pixel(100.0, 50.0);
         assert_eq!(r.origin, Point::new(0.0, 2.0, -5.0));
         assert_eq!(
@@ -139,7 +428,14 @@ mod ray_for_pixel_tests {
 mod render_tests {
     use std::f64::consts::PI;
 
-    use crate::features::{color::Color, transformations::view_transformation, vector::Vector};
+    use crate::features::{
+        color::Color,
+        control::{Progress, Stopped},
+        integrator::PathTracer,
+        occlusion::AmbientOcclusion,
+        transformations::view_transformation,
+        vector::Vector,
+    };
 
     use super::*;
     #[test]
@@ -149,8 +445,443 @@ mod render_tests {
         let from = Point::new(0.0, 0.0, -5.0);
         let to = Point::new(0.0, 0.0, 0.0);
         let up = Vector::new(0.0, 1.0, 0.0);
-        camera.transform = view_transformation(from, to, up);
-        let image = camera.render(&world);
+        camera
+            .set_transform(view_transformation(from, to, up))
+            .unwrap();
+        let image = camera.render(&world).unwrap();
         assert_eq!(image.canvas[5][5], Color::new(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn path_traced_renders_repeat() {
+        let world = World::default();
+        let mut camera = Camera::new(6.0, 4.0, PI / 2.0);
+        camera
+            .set_transform(view_transformation(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        camera.samples = 2;
+        camera.integrator = Integrator::PathTracer(PathTracer::new(4, 2));
+        camera.seed = 5;
+        let first = camera.render(&world).unwrap();
+        assert_eq!(first, camera.render(&world).unwrap());
+        camera.seed = 6;
+        assert_ne!(first, camera.render(&world).unwrap());
+    }
+
+    fn small_camera() -> Camera {
+        let mut camera = Camera::new(11.0, 7.0, PI / 2.0);
+        camera
+            .set_transform(view_transformation(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        camera.tile_size = 4;
+        camera
+    }
+
+    #[test]
+    fn every_tile_is_reported_with_its_pixels() {
+        let world = World::default();
+        let camera = small_camera();
+        let seen = Mutex::new(Canvas::new(11, 7));
+        let tiles = Mutex::new(0);
+        let frame = camera
+            .render_with(
+                &world,
+                &[],
+                &RenderControl::default(),
+                &|event| match event {
+                    RenderEvent::Tile { tile, pixels, .. } => {
+                        *tiles.lock().unwrap() += 1;
+                        let mut seen = seen.lock().unwrap();
+                        for ((x, y), color) in tile.pixels().zip(pixels) {
+                            seen.canvas[y][x] = *color;
+                        }
+                    }
+                    RenderEvent::Pass { .. } => panic!("only progressive renders report passes"),
+                },
+            )
+            .unwrap();
+        assert_eq!(*tiles.lock().unwrap(), 6);
+        assert_eq!(frame.beauty, seen.into_inner().unwrap());
+    }
+
+    #[test]
+    fn progressive_renders_refine_a_pass_at_a_time() {
+        let world = World::default();
+        let mut camera = small_camera();
+        camera.samples = 3;
+        camera.progressive = true;
+        camera.tile_order = TileOrder::Hilbert;
+        camera.integrator = Integrator::PathTracer(PathTracer::new(4, 2));
+        camera.seed = 1;
+        let images = Mutex::new(vec![]);
+        let frame = camera
+            .render_with(&world, &[], &RenderControl::default(), &|event| {
+                if let RenderEvent::Pass {
+                    pass,
+                    passes,
+                    image,
+                } = event
+                {
+                    assert_eq!(passes, 3);
+                    let mut images = images.lock().unwrap();
+                    assert_eq!(pass, images.len());
+                    images.push(image.clone());
+                }
+            })
+            .unwrap();
+        let images = images.into_inner().unwrap();
+        assert_eq!(images.len(), 3);
+        assert_ne!(images[0], images[2]);
+        assert_eq!(images[2], frame.beauty);
+        assert_eq!(frame.beauty, camera.render(&world).unwrap());
+    }
+
+    #[test]
+    fn progressive_whitted_passes_take_new_samples() {
+        let world = World::default();
+        let mut camera = small_camera();
+        camera.progressive = true;
+        assert_eq!(camera.samples, 1);
+        let mut control = RenderControl::new();
+        control.sample_budget = Some(2);
+        let images = Mutex::new(vec![]);
+        let frame = camera
+            .render_with(&world, &[], &control, &|event| {
+                if let RenderEvent::Pass { image, .. } = event {
+                    images.lock().unwrap().push(image.clone());
+                }
+            })
+            .unwrap();
+        let images = images.into_inner().unwrap();
+        assert_eq!(images.len(), 2);
+        assert_ne!(images[0], images[1]);
+        // the edges come out as two jittered samples would, not as the centre twice
+        let centred = camera.render(&world).unwrap();
+        let pixels = frame.beauty.canvas.iter().flatten();
+        assert!(pixels
+            .zip(centred.canvas.iter().flatten())
+            .any(|(blended, centre)| blended != centre));
+        camera.progressive = false;
+        camera.samples = 2;
+        assert_eq!(frame.beauty, camera.render(&world).unwrap());
+    }
+
+    #[test]
+    fn cancelled_renders_keep_finished_tiles() {
+        let world = World::default();
+        let camera = small_camera();
+        let full = camera.render(&world).unwrap();
+        let control = RenderControl::new();
+        let frame = camera
+            .render_with(&world, &[], &control, &|_| control.cancel())
+            .unwrap();
+        let progress = frame.progress;
+        assert_eq!(progress.stopped, Some(Stopped::Cancelled));
+        assert_eq!(progress.passes, 0);
+        assert!(
+            progress.pixels > 0 && progress.pixels < 77,
+            "{:?}",
+            progress
+        );
+        let mut done = 0;
+        for (y, row) in frame.beauty.canvas.iter().enumerate() {
+            for (x, color) in row.iter().enumerate() {
+                if *color == full.canvas[y][x] {
+                    done += 1;
+                } else {
+                    assert_eq!(*color, BLACK);
+                }
+            }
+        }
+        assert!(done >= progress.pixels);
+
+        let frame = camera.render_with(&world, &[], &control, &|_| {}).unwrap();
+        assert_eq!(frame.progress.pixels, 0);
+        assert_eq!(frame.beauty, Canvas::new(11, 7));
+    }
+
+    #[test]
+    fn budgets_limit_time_and_samples() {
+        let world = World::default();
+        let mut camera = small_camera();
+        camera.integrator = Integrator::PathTracer(PathTracer::new(2, 2));
+        camera.seed = 3;
+        camera.samples = 2;
+        camera.progressive = true;
+        let mut control = RenderControl::new();
+        control.sample_budget = Some(5);
+        let frame = camera.render_with(&world, &[], &control, &|_| {}).unwrap();
+        assert_eq!(
+            frame.progress,
+            Progress {
+                passes: 5,
+                pixels: 77,
+                samples: 5,
+                stopped: None
+            }
+        );
+        camera.progressive = false;
+        camera.samples = 8;
+        let frame = camera.render_with(&world, &[], &control, &|_| {}).unwrap();
+        assert_eq!((frame.progress.passes, frame.progress.samples), (1, 5));
+
+        control.time_budget = Some(std::time::Duration::ZERO);
+        let frame = camera.render_with(&world, &[], &control, &|_| {}).unwrap();
+        assert_eq!(frame.progress.stopped, Some(Stopped::OutOfTime));
+        assert_eq!(frame.progress.samples, 0);
+    }
+
+    #[test]
+    fn observers_hear_about_every_pixel_sample() {
+        use crate::features::progress::{ChannelProgress, ProgressUpdate};
+        let world = World::default();
+        let mut camera = small_camera();
+        camera.samples = 2;
+        camera.progressive = true;
+        let (observer, updates) = ChannelProgress::new();
+        camera.progress = Box::new(observer);
+        camera.render(&world).unwrap();
+        drop(camera);
+        let updates: Vec<ProgressUpdate> = updates.iter().collect();
+        assert_eq!(updates[0], ProgressUpdate::Started { total: 154 });
+        assert_eq!(updates.last(), Some(&ProgressUpdate::Finished));
+        let done: u64 = updates
+            .iter()
+            .map(|u| match u {
+                ProgressUpdate::Advanced { done } => *done,
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(done, 154);
+        // one update per tile and pass rather than per pixel
+        assert_eq!(updates.len(), 2 + 2 * 6);
+    }
+
+    #[test]
+    fn regions_render_part_of_the_full_image() {
+        let world = World::default();
+        let mut camera = small_camera();
+        camera.integrator = Integrator::PathTracer(PathTracer::new(3, 2));
+        camera.seed = 8;
+        let full = camera.render(&world).unwrap();
+        camera.region = Some(Tile {
+            x: 3,
+            y: 2,
+            width: 5,
+            height: 20,
+        });
+        assert_eq!(
+            camera.region(),
+            Tile {
+                x: 3,
+                y: 2,
+                width: 5,
+                height: 5
+            }
+        );
+        let crop = camera.render(&world).unwrap();
+        assert_eq!((crop.width, crop.height), (5, 5));
+        for (y, row) in crop.canvas.iter().enumerate() {
+            assert_eq!(row[..], full.canvas[y + 2][3..8]);
+        }
+        let mut image = Canvas::new(11, 7);
+        camera.render_into(&world, &mut image).unwrap();
+        assert_eq!(image.canvas[4][5], full.canvas[4][5]);
+        assert_eq!(image.canvas[1][5], BLACK);
+        assert_eq!(image.canvas[4][2], BLACK);
+    }
+
+    #[test]
+    fn checkpoints_resume_where_a_render_stopped() {
+        use crate::features::checkpoint::Checkpoint;
+        let world = World::default();
+        let mut camera = small_camera();
+        camera.samples = 3;
+        camera.progressive = true;
+        camera.integrator = Integrator::PathTracer(PathTracer::new(3, 2));
+        camera.seed = 4;
+        let full = camera.render(&world).unwrap();
+
+        let path = std::env::temp_dir().join(format!("rt-resume-{}.ckpt", std::process::id()));
+        let mut checkpoint = Checkpoint::new(&path, 99);
+        checkpoint.interval = std::time::Duration::ZERO;
+        let mut first = RenderControl::new();
+        first.checkpoint = Some(checkpoint.clone());
+        let tiles = Mutex::new(0);
+        let stopped = camera
+            .render_with(&world, &[], &first, &|event| {
+                let mut tiles = tiles.lock().unwrap();
+                *tiles += matches!(event, RenderEvent::Tile { .. }) as usize;
+                if *tiles == 9 {
+                    first.cancel();
+                }
+            })
+            .unwrap();
+        assert_eq!(stopped.progress.passes, 1);
+
+        // what was saved is reused rather than traced again, even in a darker world
+        let dark = World::new(crate::features::light::Light::new(
+            Point::new(0.0, 10.0, 0.0),
+            BLACK,
+        ));
+        let mut resume = RenderControl::new();
+        resume.checkpoint = Some(checkpoint.clone());
+        camera.samples = 1;
+        let reused = camera.render_with(&dark, &[], &resume, &|_| {}).unwrap();
+        assert_eq!(reused.beauty, stopped.beauty);
+
+        camera.samples = 3;
+        let resumed = camera.render_with(&world, &[], &resume, &|_| {}).unwrap();
+        assert_eq!(resumed.beauty, full);
+        assert_eq!(resumed.progress.samples, 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn renders_count_their_rays_and_tests() {
+        let world = World::default();
+        let mut camera = small_camera();
+        camera.integrator = Integrator::Whitted;
+        camera.samples = 1;
+        let frame = camera.render_passes(&world, &[]).unwrap();
+        let counters = frame.stats.counters;
+        let pixels = (camera.hsize * camera.vsize) as u64;
+        assert_eq!(counters.primary_rays, pixels);
+        // every primary ray that hits casts one shadow ray, and every ray is tested
+        // against both spheres
+        assert!(counters.shadow_rays > 0 && counters.shadow_rays < pixels);
+        assert_eq!(
+            counters.tests_of("Sphere"),
+            2 * (counters.primary_rays + counters.shadow_rays)
+        );
+        assert_eq!(counters.tests_of("Plane"), 0);
+        assert_eq!(counters.intersection_tests(), counters.tests_of("Sphere"));
+        assert_eq!(counters.secondary_rays, 0);
+        let phases: Vec<&str> = frame.stats.phases.iter().map(|(name, _)| *name).collect();
+        assert_eq!(phases, ["setup", "trace"]);
+    }
+
+    #[test]
+    fn passes_come_from_the_same_render() {
+        let world = World::default();
+        let mut camera = Camera::new(11.0, 11.0, PI / 2.0);
+        camera
+            .set_transform(view_transformation(
+                Point::new(0.0, 0.0, -5.0),
+                Point::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        let frame = camera
+            .render_passes(&world, &[Aov::Normal, Aov::Depth])
+            .unwrap();
+        assert_eq!(frame.beauty, camera.render(&world).unwrap());
+        assert_eq!(frame.passes.len(), 2);
+        let depth = frame.pass(Aov::Depth).unwrap();
+        assert_eq!(depth.canvas[5][5], Color::new(4.0, 4.0, 4.0));
+        assert_eq!(depth.canvas[0][0], BLACK);
+        let normal = frame.pass(Aov::Normal).unwrap();
+        assert_eq!(normal.canvas[5][5], Color::new(0.5, 0.5, 0.0));
+        assert!(frame.pass(Aov::Albedo).is_none());
+    }
+
+    #[test]
+    fn seeded_renders_repeat_on_any_number_of_threads() {
+        let mut world = World::default();
+        world.ambient_occlusion = Some(AmbientOcclusion::new(4, 1.0));
+        let mut camera = small_camera();
+        camera.samples = 4;
+        camera.seed = 11;
+        let render = |camera: &Camera, threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| camera.render(&world).unwrap())
+        };
+        let jittered = render(&camera, 1);
+        assert_eq!(jittered, render(&camera, 4));
+        // progressive passes draw the very samples a single pass would
+        camera.progressive = true;
+        assert_eq!(jittered, render(&camera, 3));
+        camera.progressive = false;
+        camera.integrator = Integrator::PathTracer(PathTracer::new(3, 2));
+        let traced = render(&camera, 1);
+        assert_eq!(traced, render(&camera, 4));
+        camera.seed = 12;
+        assert_ne!(traced, render(&camera, 4));
+    }
+
+    #[test]
+    fn moving_shapes_blur_over_the_shutter() {
+        use crate::features::{
+            light::Light,
+            shape::{sphere::Sphere, Shape},
+            transformations::translation,
+        };
+        let light = Light::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let mut still = World::new(light.clone());
+        still.add_shape(Box::new(Sphere::default()));
+        let mut moving = World::new(light.clone());
+        let mut sphere = Sphere::default();
+        sphere.set_transform(translation(-3.0, 0.0, 0.0)).unwrap();
+        sphere.set_motion(Some(translation(3.0, 0.0, 0.0))).unwrap();
+        moving.add_shape(Box::new(sphere));
+        let mut camera = small_camera();
+        camera.samples = 64;
+        camera.integrator = Integrator::PathTracer(PathTracer::new(64, 1));
+        camera.seed = 0;
+        // the sphere only crosses the middle pixel for about a third of the shutter
+        let sharp = camera.pixel_color(&still, 5.0, 3.0).unwrap().rgb.y;
+        let blurred = camera.pixel_color(&moving, 5.0, 3.0).unwrap().rgb.y;
+        assert!(blurred > 0.1 * sharp && blurred < 0.6 * sharp);
+        // still scenes draw the same samples they always did
+        assert!(!still.is_moving());
+        let mut parked = World::new(light.clone());
+        let mut sphere = Sphere::default();
+        sphere
+            .set_motion(Some(Matrice::identity_matrix(4)))
+            .unwrap();
+        sphere.set_motion(None).unwrap();
+        parked.add_shape(Box::new(sphere));
+        assert_eq!(
+            camera.render(&parked).unwrap(),
+            camera.render(&still).unwrap()
+        );
+    }
+
+    #[test]
+    fn single_whitted_samples_still_blur() {
+        use crate::features::{
+            light::Light,
+            shape::{sphere::Sphere, Shape},
+            transformations::translation,
+        };
+        let light = Light::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let mut opened = World::new(light.clone());
+        let mut sphere = Sphere::default();
+        sphere.set_transform(translation(-3.0, 0.0, 0.0)).unwrap();
+        opened.add_shape(Box::new(sphere));
+        let mut moving = World::new(light);
+        let mut sphere = Sphere::default();
+        sphere.set_transform(translation(-3.0, 0.0, 0.0)).unwrap();
+        sphere.set_motion(Some(translation(3.0, 0.0, 0.0))).unwrap();
+        moving.add_shape(Box::new(sphere));
+        let camera = small_camera();
+        assert_eq!(camera.samples, 1);
+        assert_eq!(camera.integrator, Integrator::Whitted);
+        // a render frozen where the shutter opened would miss the sweep
+        assert_ne!(
+            camera.render(&moving).unwrap(),
+            camera.render(&opened).unwrap()
+        );
+    }
 }
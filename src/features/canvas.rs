@@ -1,5 +1,16 @@
-use super::{color::Color, consts::BLACK};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
 
+use super::{
+    color::Color,
+    consts::BLACK,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -17,13 +28,383 @@ impl Canvas {
     // pub fn write_pixel(&mut self, x: usize, y: usize, color: Color) {
     //     self.canvas[y][x] = color;
     // }
+    // copies another canvas over this one with its top left corner at x, y,
+    // dropping whatever falls off the edge
+    pub fn paste(&mut self, other: &Canvas, x: usize, y: usize) {
+        for (row, source) in self.canvas.iter_mut().skip(y).zip(&other.canvas) {
+            for (pixel, color) in row.iter_mut().skip(x).zip(source) {
+                *pixel = *color;
+            }
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Canvas::decode(&fs::read(path)?)
+    }
+    // an hdr or ppm image, told apart by its header
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.starts_with(b"#?") {
+            return Canvas::from_hdr(data);
+        }
+        Canvas::from_ppm(data)
+    }
+    // radiance rgbe (.hdr) images, flat or with per-channel run length encoded scanlines
+    pub fn from_hdr(data: &[u8]) -> Result<Self> {
+        let mut pos = 0;
+        let mut format_ok = true;
+        loop {
+            let line = hdr_line(data, &mut pos)?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                format_ok = format == "32-bit_rle_rgbe";
+            }
+        }
+        if !format_ok {
+            return Err(Error::parse(
+                "unsupported hdr format, expected 32-bit_rle_rgbe",
+            ));
+        }
+        let resolution = hdr_line(data, &mut pos)?;
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", h, "+X", w] => match (h.parse::<usize>(), w.parse::<usize>()) {
+                (Ok(h), Ok(w)) => (h, w),
+                _ => return Err(Error::parse(format!("invalid hdr size '{}'", resolution))),
+            },
+            _ => {
+                return Err(Error::parse(format!(
+                    "unsupported hdr orientation '{}', expected -Y <height> +X <width>",
+                    resolution
+                )))
+            }
+        };
+        // a scanline takes at least four bytes and runs pack at most 16 pixels into a
+        // byte, so the size is checked against the data before it is allocated
+        let needed = height.max(1).checked_mul(width.div_ceil(16).max(4));
+        if !needed.is_some_and(|n| n <= data.len() - pos) {
+            return Err(Error::parse("hdr pixel data is truncated"));
+        }
+        let mut canvas = Canvas::new(width, height);
+        let mut scanline = vec![[0u8; 4]; width];
+        for row in canvas.canvas.iter_mut() {
+            hdr_scanline(data, &mut pos, &mut scanline)?;
+            for (pixel, rgbe) in row.iter_mut().zip(&scanline) {
+                *pixel = rgbe_color(*rgbe);
+            }
+        }
+        Ok(canvas)
+    }
+
+    // reads plain (P3) and binary (P6) ppm images
+    pub fn from_ppm(data: &[u8]) -> Result<Self> {
+        let mut pos = 0;
+        let magic = ppm_token(data, &mut pos)?;
+        if magic != "P3" && magic != "P6" {
+            return Err(Error::parse(format!(
+                "unsupported image format '{}', expected P3 or P6 ppm",
+                magic
+            )));
+        }
+        let width = ppm_number(data, &mut pos)?;
+        let height = ppm_number(data, &mut pos)?;
+        let max = ppm_number(data, &mut pos)?;
+        if max == 0 || max > 255 {
+            return Err(Error::parse(format!(
+                "unsupported ppm maximum value {}",
+                max
+            )));
+        }
+        let scale = max as f64;
+        // every pixel takes at least three bytes, so the size is checked against the data
+        // before it is allocated; empty rows count too, as they still cost memory
+        let needed = width
+            .max(1)
+            .checked_mul(height.max(1))
+            .and_then(|n| n.checked_mul(3));
+        if !needed.is_some_and(|n| n <= data.len().saturating_sub(pos)) {
+            return Err(Error::parse("ppm pixel data is truncated"));
+        }
+        let mut canvas = Canvas::new(width, height);
+        if magic == "P6" {
+            // exactly one whitespace byte separates the header from the pixel data
+            pos += 1;
+            let pixels = data
+                .get(pos..pos + width * height * 3)
+                .ok_or_else(|| Error::parse("ppm pixel data is truncated"))?;
+            for (ix, rgb) in pixels.chunks(3).enumerate() {
+                canvas.canvas[ix / width][ix % width] = Color::new(
+                    rgb[0] as f64 / scale,
+                    rgb[1] as f64 / scale,
+                    rgb[2] as f64 / scale,
+                );
+            }
+            return Ok(canvas);
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let r = ppm_number(data, &mut pos)? as f64;
+                let g = ppm_number(data, &mut pos)? as f64;
+                let b = ppm_number(data, &mut pos)? as f64;
+                canvas.canvas[y][x] = Color::new(r / scale, g / scale, b / scale);
+            }
+        }
+        Ok(canvas)
+    }
+
+    pub fn ppm_string(&self) -> String {
+        let mut out = format!("P3\n{} {}\n255\n", self.width, self.height);
+        for line in self.canvas.iter() {
+            for pixel in line {
+                out.push_str(&pixel.clamp().rgb.as_str());
+            }
+        }
+        out
+    }
+
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.ppm_string().as_bytes())
+    }
 
-    pub fn to_ppm(&self) {
-        print!("P3\n{} {}\n255\n", self.width, self.height);
-        for line in self.canvas.clone().into_iter() {
+    pub fn write_bmp<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let row_size = (self.width * 3 + 3) & !3;
+        let data_size = row_size * self.height;
+        let file_size = 54 + data_size;
+        let mut out = Vec::with_capacity(file_size);
+        out.extend_from_slice(b"BM");
+        out.extend_from_slice(&(file_size as u32).to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&54u32.to_le_bytes());
+        out.extend_from_slice(&40u32.to_le_bytes());
+        out.extend_from_slice(&(self.width as i32).to_le_bytes());
+        out.extend_from_slice(&(self.height as i32).to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&24u16.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&(data_size as u32).to_le_bytes());
+        out.extend_from_slice(&2835i32.to_le_bytes());
+        out.extend_from_slice(&2835i32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        // bmp rows are stored bottom-up in BGR order
+        for line in self.canvas.iter().rev() {
+            let start = out.len();
             for pixel in line {
-                print!("{}", pixel.clamp().rgb.as_str())
+                let c = pixel.clamp().rgb;
+                out.extend_from_slice(&[c.z as u8, c.y as u8, c.x as u8]);
             }
+            out.resize(start + row_size, 0);
         }
+        writer.write_all(&out)
+    }
+}
+
+fn hdr_line<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a str> {
+    let rest = data
+        .get(*pos..)
+        .ok_or_else(|| Error::parse("hdr header is truncated"))?;
+    let end = rest
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| Error::parse("hdr header is truncated"))?;
+    *pos += end + 1;
+    std::str::from_utf8(&rest[..end])
+        .map(str::trim)
+        .map_err(|_| Error::parse("hdr header is not text"))
+}
+
+fn hdr_byte(data: &[u8], pos: &mut usize) -> Result<u8> {
+    let byte = *data
+        .get(*pos)
+        .ok_or_else(|| Error::parse("hdr pixel data is truncated"))?;
+    *pos += 1;
+    Ok(byte)
+}
+
+fn hdr_scanline(data: &[u8], pos: &mut usize, scanline: &mut [[u8; 4]]) -> Result<()> {
+    let width = scanline.len();
+    let header = data.get(*pos..*pos + 4);
+    // new style run length encoding starts with 2, 2 and the width; anything else is flat
+    let encoded = match header {
+        Some([2, 2, hi, lo]) => {
+            (8..0x8000).contains(&width) && (*hi as usize) << 8 | *lo as usize == width
+        }
+        _ => false,
+    };
+    if !encoded {
+        for pixel in scanline.iter_mut() {
+            for channel in pixel.iter_mut() {
+                *channel = hdr_byte(data, pos)?;
+            }
+        }
+        return Ok(());
+    }
+    *pos += 4;
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = hdr_byte(data, pos)? as usize;
+            if count > 128 {
+                let run = count - 128;
+                let value = hdr_byte(data, pos)?;
+                if x + run > width {
+                    return Err(Error::parse("hdr run overflows its scanline"));
+                }
+                for pixel in &mut scanline[x..x + run] {
+                    pixel[channel] = value;
+                }
+                x += run;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(Error::parse("hdr run overflows its scanline"));
+                }
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[channel] = hdr_byte(data, pos)?;
+                }
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+// a shared exponent scales all three mantissas
+fn rgbe_color([r, g, b, e]: [u8; 4]) -> Color {
+    if e == 0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let scale = 2f64.powi(e as i32 - (128 + 8));
+    Color::new(r as f64 * scale, g as f64 * scale, b as f64 * scale)
+}
+
+fn ppm_token<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a str> {
+    loop {
+        match data.get(*pos) {
+            Some(b'#') => {
+                while !matches!(data.get(*pos), None | Some(b'\n')) {
+                    *pos += 1;
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => *pos += 1,
+            Some(_) => break,
+            None => return Err(Error::parse("unexpected end of ppm data")),
+        }
+    }
+    let start = *pos;
+    while matches!(data.get(*pos), Some(c) if !c.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    std::str::from_utf8(&data[start..*pos]).map_err(|_| Error::parse("invalid ppm header"))
+}
+
+fn ppm_number(data: &[u8], pos: &mut usize) -> Result<usize> {
+    let token = ppm_token(data, pos)?;
+    token
+        .parse()
+        .map_err(|_| Error::parse(format!("invalid number '{}' in ppm data", token)))
+}
+
+#[cfg(test)]
+mod canvas_tests {
+    use super::*;
+
+    #[test]
+    fn ppm_header_and_pixels() {
+        let mut c = Canvas::new(2, 1);
+        c.canvas[0][1] = Color::new(1.5, 0.5, -0.5);
+        assert_eq!(c.ppm_string(), "P3\n2 1\n255\n0 0 0\n255 127 0\n");
+    }
+
+    #[test]
+    fn read_plain_ppm() {
+        let c = Canvas::from_ppm(b"P3\n# comment\n2 1\n255\n255 0 0\n0 0 51\n").unwrap();
+        assert_eq!(c.width, 2);
+        assert_eq!(c.height, 1);
+        assert_eq!(c.canvas[0][0], Color::new(1.0, 0.0, 0.0));
+        assert_eq!(c.canvas[0][1], Color::new(0.0, 0.0, 0.2));
+    }
+
+    #[test]
+    fn read_binary_ppm() {
+        let mut data = b"P6\n1 2\n255\n".to_vec();
+        data.extend_from_slice(&[255, 255, 255, 0, 51, 0]);
+        let c = Canvas::from_ppm(&data).unwrap();
+        assert_eq!(c.canvas[0][0], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c.canvas[1][0], Color::new(0.0, 0.2, 0.0));
+    }
+
+    #[test]
+    fn ppm_round_trip() {
+        let mut c = Canvas::new(3, 2);
+        c.canvas[1][2] = Color::new(1.0, 0.2, 0.4);
+        let read = Canvas::from_ppm(c.ppm_string().as_bytes()).unwrap();
+        assert_eq!(read, c);
+    }
+
+    #[test]
+    fn reject_truncated_ppm() {
+        assert!(Canvas::from_ppm(b"P3\n2 2\n255\n0 0 0").is_err());
+        assert!(Canvas::from_ppm(b"P5\n2 2\n255\n").is_err());
+        // sizes the data cannot hold are refused before anything is allocated
+        assert!(Canvas::from_ppm(b"P6\n100000 100000\n255\n\0\0\0").is_err());
+        assert!(Canvas::from_ppm(b"P3\n1 100000000000\n255\n0 0 0").is_err());
+        let huge = format!("P6\n{} 3\n255\n", usize::MAX / 2);
+        assert!(Canvas::from_ppm(huge.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn bmp_rows_are_padded() {
+        let c = Canvas::new(3, 2);
+        let mut out = vec![];
+        c.write_bmp(&mut out).unwrap();
+        assert_eq!(&out[0..2], b"BM");
+        assert_eq!(out.len(), 54 + 12 * 2);
+    }
+
+    #[test]
+    fn paste_clips_at_the_edges() {
+        let mut canvas = Canvas::new(4, 3);
+        let mut patch = Canvas::new(2, 2);
+        patch.canvas = vec![vec![Color::new(1.0, 1.0, 1.0); 2]; 2];
+        canvas.paste(&patch, 3, 1);
+        assert_eq!(canvas.canvas[1][3], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(canvas.canvas[2][3], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(canvas.canvas[1][2], BLACK);
+        assert_eq!(canvas.canvas[0][3], BLACK);
+    }
+
+    #[test]
+    fn read_flat_hdr() {
+        let mut data = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        // 128 * 2^(129 - 136) = 1 and 64 * 2^(131 - 136) = 2
+        data.extend([128, 0, 128, 129, 64, 64, 0, 131]);
+        let c = Canvas::from_hdr(&data).unwrap();
+        assert_eq!(c.canvas[0][0], Color::new(1.0, 0.0, 1.0));
+        assert_eq!(c.canvas[0][1], Color::new(2.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn read_run_length_encoded_hdr() {
+        let mut data = b"#?RGBE\n\n-Y 1 +X 8\n".to_vec();
+        data.extend([2, 2, 0, 8]);
+        // red: a run of eight; green: eight literals; blue: two runs; exponent: one run
+        data.extend([136, 128]);
+        data.push(8);
+        data.extend([0, 16, 32, 48, 64, 80, 96, 112]);
+        data.extend([132, 0, 132, 255]);
+        data.extend([136, 129]);
+        let c = Canvas::from_hdr(&data).unwrap();
+        assert_eq!(c.canvas[0][0], Color::new(1.0, 0.0, 0.0));
+        assert_eq!(c.canvas[0][7], Color::new(1.0, 0.875, 255.0 / 128.0));
+    }
+
+    #[test]
+    fn reject_bad_hdr() {
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n+Y 1 +X 1\n\0\0\0\0").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 2 +X 1\n\0\0\0\x80").is_err());
+        // sizes the data cannot hold are refused before anything is allocated
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 100000 +X 100000\n\0\0\0\0").is_err());
+        assert!(Canvas::from_hdr(b"#?RADIANCE\n\n-Y 100000000000 +X 0\n").is_err());
     }
 }
@@ -0,0 +1,277 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use super::{
+    aov::{Aov, Frame},
+    canvas::Canvas,
+    color::Color,
+    consts::BLACK,
+    control::Progress,
+    error::{Error, Result},
+    stats::Stats,
+};
+
+const MAGIC: &[u8; 8] = b"RTCKPT1\n";
+
+// where a long render saves its samples so a later run can pick up from them
+#[derive(Debug, PartialEq, Clone)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    // identifies the scene, usually the hash of its source; a checkpoint only resumes a
+    // render of the same scene through the same camera
+    pub scene_hash: u64,
+    // how often finished tiles are saved while rendering
+    pub interval: Duration,
+}
+
+impl Checkpoint {
+    pub fn new(path: impl Into<PathBuf>, scene_hash: u64) -> Self {
+        Checkpoint {
+            path: path.into(),
+            scene_hash,
+            interval: Duration::from_secs(60),
+        }
+    }
+
+    // keyed by a stable hash of whatever identifies the scene, such as its source
+    pub fn for_scene(path: impl Into<PathBuf>, scene: &[u8]) -> Self {
+        Checkpoint::new(path, fnv1a(scene))
+    }
+}
+
+// 64 bit FNV-1a, stable across runs and platforms unlike the std hasher
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// running sums of every pixel's samples, shared by the render threads
+pub struct Accumulator {
+    pub total: Vec<Vec<Color>>,
+    pub counts: Vec<Vec<usize>>,
+    pub frame: Frame,
+}
+
+impl Accumulator {
+    pub fn new(width: usize, height: usize, aovs: &[Aov]) -> Self {
+        Accumulator {
+            total: vec![vec![BLACK; width]; height],
+            counts: vec![vec![0; width]; height],
+            frame: Frame {
+                beauty: Canvas::new(width, height),
+                passes: aovs
+                    .iter()
+                    .map(|aov| (*aov, Canvas::new(width, height)))
+                    .collect(),
+                progress: Progress::default(),
+                stats: Stats::default(),
+            },
+        }
+    }
+
+    // a copy of what a checkpoint keeps, so the render threads need not wait while it
+    // is written
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            width: self.frame.beauty.width,
+            height: self.frame.beauty.height,
+            total: self.total.clone(),
+            counts: self.counts.clone(),
+            passes: self
+                .frame
+                .passes
+                .iter()
+                .map(|(_, pass)| pass.clone())
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, checkpoint: &Checkpoint, key: u64) -> Result<()> {
+        self.snapshot().save(checkpoint, key)
+    }
+
+    // the saved samples, or nothing when there is no checkpoint yet or it belongs to
+    // another scene or camera
+    pub fn load(
+        checkpoint: &Checkpoint,
+        key: u64,
+        width: usize,
+        height: usize,
+        aovs: &[Aov],
+    ) -> Result<Option<Self>> {
+        let mut data = vec![];
+        match fs::File::open(&checkpoint.path) {
+            Ok(mut file) => file.read_to_end(&mut data)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let corrupt = || Error::parse(format!("{}: corrupt checkpoint", checkpoint.path.display()));
+        if !data.starts_with(MAGIC) {
+            return Err(corrupt());
+        }
+        let mut words = data[MAGIC.len()..]
+            .chunks_exact(8)
+            .map(|w| u64::from_le_bytes(w.try_into().unwrap()));
+        let mut next = || words.next().ok_or_else(corrupt);
+        let header = [next()?, next()?, next()?, next()?];
+        if header != [key, width as u64, height as u64, aovs.len() as u64] {
+            return Ok(None);
+        }
+        let mut samples = Accumulator::new(width, height, aovs);
+        for y in 0..height {
+            for x in 0..width {
+                samples.counts[y][x] = next()? as usize;
+                samples.total[y][x] = read_color(&mut next)?;
+                if samples.counts[y][x] > 0 {
+                    samples.frame.beauty.canvas[y][x] =
+                        samples.total[y][x] * (1.0 / samples.counts[y][x] as f64);
+                }
+            }
+        }
+        for (_, pass) in samples.frame.passes.iter_mut() {
+            for pixel in pass.canvas.iter_mut().flatten() {
+                *pixel = read_color(&mut next)?;
+            }
+        }
+        if next().is_ok() {
+            return Err(corrupt());
+        }
+        Ok(Some(samples))
+    }
+}
+
+pub struct Snapshot {
+    width: usize,
+    height: usize,
+    total: Vec<Vec<Color>>,
+    counts: Vec<Vec<usize>>,
+    passes: Vec<Canvas>,
+}
+
+impl Snapshot {
+    // written next to the checkpoint then moved over it, so a crash mid-save leaves
+    // the previous checkpoint intact
+    pub fn save(&self, checkpoint: &Checkpoint, key: u64) -> Result<()> {
+        let mut data = MAGIC.to_vec();
+        for n in [
+            key,
+            self.width as u64,
+            self.height as u64,
+            self.passes.len() as u64,
+        ] {
+            data.extend(n.to_le_bytes());
+        }
+        for (sums, counts) in self.total.iter().zip(&self.counts) {
+            for (sum, count) in sums.iter().zip(counts) {
+                data.extend((*count as u64).to_le_bytes());
+                push_color(&mut data, sum);
+            }
+        }
+        for pass in &self.passes {
+            pass.canvas
+                .iter()
+                .flatten()
+                .for_each(|color| push_color(&mut data, color));
+        }
+        // the whole name is kept, so checkpoints differing only in their extension, or
+        // already ending in .partial, never share a temporary file
+        let mut partial = checkpoint.path.clone().into_os_string();
+        partial.push(".partial");
+        fs::File::create(&partial)?.write_all(&data)?;
+        fs::rename(&partial, &checkpoint.path)?;
+        Ok(())
+    }
+}
+
+fn read_color(next: &mut impl FnMut() -> Result<u64>) -> Result<Color> {
+    let [r, g, b] = [next()?, next()?, next()?].map(f64::from_bits);
+    Ok(Color::new(r, g, b))
+}
+
+fn push_color(data: &mut Vec<u8>, color: &Color) {
+    for channel in [color.rgb.x, color.rgb.y, color.rgb.z] {
+        data.extend(channel.to_bits().to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use super::*;
+
+    fn temp(name: &str) -> Checkpoint {
+        let path = std::env::temp_dir().join(format!("rt-{}-{}.ckpt", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        Checkpoint::new(path, 1)
+    }
+
+    #[test]
+    fn fnv_matches_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(
+            Checkpoint::for_scene("a.ckpt", b"a").scene_hash,
+            0xaf63_dc4c_8601_ec8c
+        );
+    }
+
+    #[test]
+    fn samples_round_trip() {
+        let checkpoint = temp("round-trip");
+        let mut samples = Accumulator::new(3, 2, &[Aov::Depth]);
+        samples.total[1][2] = Color::new(0.1, 2.0, 30.0);
+        samples.counts[1][2] = 4;
+        samples.frame.passes[0].1.canvas[0][1] = Color::new(5.0, 5.0, 5.0);
+        samples.save(&checkpoint, 42).unwrap();
+        let loaded = Accumulator::load(&checkpoint, 42, 3, 2, &[Aov::Depth])
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.total, samples.total);
+        assert_eq!(loaded.counts, samples.counts);
+        assert_eq!(loaded.frame.passes, samples.frame.passes);
+        assert_eq!(
+            loaded.frame.beauty.canvas[1][2],
+            Color::new(0.025, 0.5, 7.5)
+        );
+        // another scene or camera, or another image size, starts over
+        assert!(Accumulator::load(&checkpoint, 43, 3, 2, &[Aov::Depth])
+            .unwrap()
+            .is_none());
+        assert!(Accumulator::load(&checkpoint, 42, 2, 3, &[Aov::Depth])
+            .unwrap()
+            .is_none());
+        fs::remove_file(&checkpoint.path).unwrap();
+        assert!(Accumulator::load(&checkpoint, 42, 3, 2, &[])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn saves_leave_files_with_other_extensions_alone() {
+        let checkpoint = temp("extension");
+        let neighbour = checkpoint.path.with_extension("partial");
+        fs::write(&neighbour, "keep").unwrap();
+        Accumulator::new(2, 2, &[]).save(&checkpoint, 7).unwrap();
+        assert_eq!(fs::read_to_string(&neighbour).unwrap(), "keep");
+        // a checkpoint named like a temporary file still saves through another one
+        let named = Checkpoint::new(&neighbour, 1);
+        Accumulator::new(2, 2, &[]).save(&named, 7).unwrap();
+        assert!(Accumulator::load(&named, 7, 2, 2, &[]).unwrap().is_some());
+        fs::remove_file(&checkpoint.path).unwrap();
+        fs::remove_file(&neighbour).unwrap();
+    }
+
+    #[test]
+    fn truncated_checkpoints_are_rejected() {
+        let checkpoint = temp("truncated");
+        Accumulator::new(2, 2, &[]).save(&checkpoint, 7).unwrap();
+        let data = fs::read(&checkpoint.path).unwrap();
+        fs::write(&checkpoint.path, &data[..data.len() - 3]).unwrap();
+        assert!(Accumulator::load(&checkpoint, 7, 2, 2, &[]).is_err());
+        fs::remove_file(&checkpoint.path).unwrap();
+    }
+}
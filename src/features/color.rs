@@ -12,27 +12,9 @@ impl Color {
         Color::from(Tuple::new(x, y, z))
     }
     pub fn clamp(&self) -> Self {
-        let mut x = self.rgb.x * 255.0;
-        let mut y = self.rgb.y * 255.0;
-        let mut z = self.rgb.z * 255.0;
-        if x > 255.0 {
-            x = 255.0;
-        }
-        if x < 0.0 {
-            x = 0.0;
-        }
-        if y > 255.0 {
-            y = 255.0;
-        }
-        if y < 0.0 {
-            y = 0.0;
-        }
-        if z > 255.0 {
-            z = 255.0;
-        }
-        if z < 0.0 {
-            z = 0.0;
-        }
+        let x = (self.rgb.x * 255.0).clamp(0.0, 255.0);
+        let y = (self.rgb.y * 255.0).clamp(0.0, 255.0);
+        let z = (self.rgb.z * 255.0).clamp(0.0, 255.0);
         Color::from(Tuple::new(x, y, z))
     }
 }
@@ -11,13 +11,27 @@ pub struct Computation {
     pub eyev: Vector,
     pub normalv: Vector,
     pub inside: bool,
+    // the time of the ray, so shading sees moving shapes where the ray met them
+    pub time: f64,
 }
 
 impl Computation {
     pub fn new(ray: &Ray, i: &Intersection, s: &dyn Shape) -> Self {
         let point = ray.position(i.t);
         let eyev = -ray.direction;
-        let mut normalv = s.normal_at(point).unwrap_or_default();
+        if s.is_volume() {
+            return Computation {
+                t: i.t,
+                object_id: i.shape_id.clone(),
+                point,
+                over_point: point,
+                eyev,
+                normalv: eyev,
+                inside: false,
+                time: ray.time,
+            };
+        }
+        let mut normalv = s.normal_at(point);
         let inside: bool;
         if normalv.dot_product(&eyev) < 0.0 {
             inside = true;
@@ -25,14 +39,20 @@ impl Computation {
         } else {
             inside = false;
         }
+        // the point is still pushed off the true surface, only shading sees the modifier
+        let over_point = point + normalv * EPSILON;
+        if let Some(modifier) = &s.get_material().normal {
+            normalv = modifier.apply(s, &point, normalv);
+        }
         Computation {
             t: i.t,
             object_id: i.shape_id.clone(),
             point,
-            over_point: point + normalv * EPSILON,
+            over_point,
             eyev,
             normalv,
             inside,
+            time: ray.time,
         }
     }
 }
@@ -0,0 +1,95 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use super::checkpoint::Checkpoint;
+
+// steers a running render: clones share the cancel flag, so one can be handed to
+// another thread to stop the render early
+#[derive(Debug, Clone, Default)]
+pub struct RenderControl {
+    cancelled: Arc<AtomicBool>,
+    // wall clock time after which no more tiles are started
+    pub time_budget: Option<Duration>,
+    // the most samples any pixel gets; progressive renders keep refining up to it
+    pub sample_budget: Option<usize>,
+    // saves samples as the render goes and resumes from what an earlier run saved
+    pub checkpoint: Option<Checkpoint>,
+}
+
+impl RenderControl {
+    pub fn new() -> Self {
+        RenderControl::default()
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    // why a render that started at the given time should stop, if it should
+    pub fn should_stop(&self, started: Instant) -> Option<Stopped> {
+        if self.is_cancelled() {
+            return Some(Stopped::Cancelled);
+        }
+        match self.time_budget {
+            Some(budget) if started.elapsed() >= budget => Some(Stopped::OutOfTime),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Stopped {
+    Cancelled,
+    OutOfTime,
+}
+
+// how far a render got before it finished or was stopped
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Progress {
+    // passes over the whole image that were completed
+    pub passes: usize,
+    // pixels that got at least one sample
+    pub pixels: usize,
+    // the fewest samples any pixel got
+    pub samples: usize,
+    pub stopped: Option<Stopped>,
+}
+
+#[cfg(test)]
+mod control_tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let control = RenderControl::new();
+        let handle = control.clone();
+        let started = Instant::now();
+        assert_eq!(control.should_stop(started), None);
+        std::thread::spawn(move || handle.cancel()).join().unwrap();
+        assert!(control.is_cancelled());
+        assert_eq!(control.should_stop(started), Some(Stopped::Cancelled));
+    }
+
+    #[test]
+    fn time_budgets_run_out() {
+        let control = RenderControl {
+            time_budget: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert_eq!(
+            control.should_stop(Instant::now()),
+            Some(Stopped::OutOfTime)
+        );
+        let control = RenderControl {
+            time_budget: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        assert_eq!(control.should_stop(Instant::now()), None);
+    }
+}
@@ -0,0 +1,73 @@
+use std::{fmt, io};
+
+use super::matrice::Matrice;
+
+#[derive(Debug)]
+pub enum Error {
+    SingularTransform(Matrice),
+    UnknownShape(String),
+    InvalidMaterial(String),
+    Io(io::Error),
+    Parse {
+        line: Option<usize>,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn parse(message: impl Into<String>) -> Self {
+        Error::Parse {
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at_line(self, line: usize) -> Self {
+        match self {
+            Error::Parse {
+                line: None,
+                message,
+            } => Error::Parse {
+                line: Some(line),
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SingularTransform(_) => write!(f, "transform is not invertible"),
+            Error::UnknownShape(id) => write!(f, "unknown shape id '{}'", id),
+            Error::InvalidMaterial(message) => write!(f, "invalid material: {}", message),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse {
+                line: Some(line),
+                message,
+            } => write!(f, "line {}: {}", line, message),
+            Error::Parse {
+                line: None,
+                message,
+            } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
@@ -0,0 +1,583 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::{Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{
+    aov::Frame,
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    control::Progress,
+    error::Result,
+    stats::{Counters, Stats},
+    tiles::{RenderEvent, Tile},
+    world::World,
+};
+
+const MAGIC: &[u8; 8] = b"RTFARM2\n";
+const DONE: u64 = 0;
+const TILE: u64 = 1;
+// caps on what the other side may make us read, far above any real scene, so a bad
+// peer cannot have us allocate without end
+const MAX_STRING: u64 = 1 << 26;
+const MAX_ARGS: u64 = 1 << 12;
+const MAX_FILE: u64 = 1 << 30;
+const MAX_FILES: u64 = 1 << 12;
+
+// everything a worker needs to render its share of the image
+#[derive(Debug, PartialEq, Clone)]
+pub struct Job {
+    // the scene source, parsed again by every worker
+    pub scene: String,
+    // the coordinator's command line, so workers can set their camera up the same way
+    pub args: Vec<String>,
+    // the files the scene loads, such as images, under the coordinator's paths and
+    // with their contents, as workers need not share its disk
+    pub files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+// hands tiles to the workers that connect over TCP and puts their pixels together.
+// A worker that drops its connection or goes quiet has its tile handed to another
+pub struct Coordinator {
+    listener: TcpListener,
+    // how long one worker may take over one tile before it is given up on, and how
+    // long the render waits for any tile to come back before it fails
+    pub timeout: Duration,
+}
+
+struct Queue {
+    pending: VecDeque<Tile>,
+    remaining: usize,
+    // when the last tile came back, or the render started
+    progressed: Instant,
+    abandoned: bool,
+}
+
+impl Coordinator {
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Self> {
+        Ok(Coordinator {
+            listener: TcpListener::bind(address)?,
+            timeout: Duration::from_secs(600),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    // renders the camera's region with whichever workers connect, reporting every
+    // tile as it comes back; returns once every tile is in, with the work the workers
+    // counted
+    pub fn render(
+        &self,
+        job: &Job,
+        camera: &Camera,
+        on_event: &(dyn Fn(RenderEvent) + Sync),
+    ) -> Result<Frame> {
+        let started = Instant::now();
+        let region = camera.region();
+        let tiles = camera.tiles();
+        let queue = Mutex::new(Queue {
+            remaining: tiles.len(),
+            pending: tiles.into(),
+            progressed: started,
+            abandoned: false,
+        });
+        let changed = Condvar::new();
+        let image = Mutex::new((
+            Canvas::new(region.width, region.height),
+            Counters::default(),
+        ));
+        let finished = |tile: &Tile, pixels: &[Color], counters: Counters| {
+            let mut image = image.lock().unwrap();
+            for ((x, y), color) in tile.pixels().zip(pixels) {
+                image.0.canvas[y - region.y][x - region.x] = *color;
+            }
+            image.1 += counters;
+            drop(image);
+            camera.progress.advance(pixels.len() as u64);
+            on_event(RenderEvent::Tile {
+                pass: 0,
+                tile,
+                pixels,
+            });
+        };
+        camera.progress.start((region.width * region.height) as u64);
+        self.listener.set_nonblocking(true)?;
+        thread::scope(|scope| loop {
+            {
+                let mut queue = queue.lock().unwrap();
+                if queue.remaining == 0 {
+                    break;
+                }
+                // without workers bringing tiles back the render would wait forever
+                if queue.progressed.elapsed() > self.timeout {
+                    queue.abandoned = true;
+                    changed.notify_all();
+                    break;
+                }
+            }
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    scope.spawn(|| {
+                        // a worker that fails is simply done; its tile went back
+                        let _ = serve(stream, job, self.timeout, &queue, &changed, &finished);
+                    });
+                }
+                // connections that fail before they are accepted are nobody's loss
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        });
+        camera.progress.finish();
+        let queue = queue.into_inner().unwrap();
+        if queue.abandoned {
+            let message = format!(
+                "no worker finished a tile in {:?}, {} of {} tiles left",
+                self.timeout,
+                queue.remaining,
+                camera.tiles().len()
+            );
+            return Err(io::Error::new(io::ErrorKind::TimedOut, message).into());
+        }
+        let (beauty, counters) = image.into_inner().unwrap();
+        Ok(Frame {
+            beauty,
+            passes: vec![],
+            progress: Progress {
+                passes: 1,
+                pixels: region.width * region.height,
+                samples: camera.samples.max(1),
+                stopped: None,
+            },
+            stats: Stats {
+                counters,
+                phases: vec![("farm", started.elapsed())],
+            },
+        })
+    }
+}
+
+fn serve(
+    stream: TcpStream,
+    job: &Job,
+    timeout: Duration,
+    queue: &Mutex<Queue>,
+    changed: &Condvar,
+    finished: &(dyn Fn(&Tile, &[Color], Counters) + Sync),
+) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    // a worker that stops reading must not hold its tile any longer than one that
+    // stops writing
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    writer.write_all(MAGIC)?;
+    write_string(&mut writer, &job.scene)?;
+    write_u64(&mut writer, job.args.len() as u64)?;
+    for arg in &job.args {
+        write_string(&mut writer, arg)?;
+    }
+    write_u64(&mut writer, job.files.len() as u64)?;
+    for (path, data) in &job.files {
+        write_string(&mut writer, &path.to_string_lossy())?;
+        write_bytes(&mut writer, data)?;
+    }
+    writer.flush()?;
+    loop {
+        let tile = {
+            let mut queue = changed
+                .wait_while(queue.lock().unwrap(), |queue| {
+                    queue.pending.is_empty() && queue.remaining > 0 && !queue.abandoned
+                })
+                .unwrap();
+            match queue.pending.pop_front() {
+                Some(tile) if !queue.abandoned => tile,
+                _ => break,
+            }
+        };
+        match assign(&mut reader, &mut writer, &tile) {
+            Ok((pixels, counters)) => {
+                finished(&tile, &pixels, counters);
+                let mut queue = queue.lock().unwrap();
+                queue.remaining -= 1;
+                queue.progressed = Instant::now();
+            }
+            Err(e) => {
+                queue.lock().unwrap().pending.push_front(tile);
+                changed.notify_all();
+                return Err(e.into());
+            }
+        }
+        changed.notify_all();
+    }
+    write_u64(&mut writer, DONE)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn assign(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    tile: &Tile,
+) -> io::Result<(Vec<Color>, Counters)> {
+    for n in [
+        TILE,
+        tile.x as u64,
+        tile.y as u64,
+        tile.width as u64,
+        tile.height as u64,
+    ] {
+        write_u64(writer, n)?;
+    }
+    writer.flush()?;
+    // the worker names the tile it rendered, which catches workers whose camera
+    // came out different
+    let rendered = read_tile(reader)?;
+    if rendered != *tile {
+        return Err(invalid("worker rendered a different tile"));
+    }
+    let pixels = (0..tile.width * tile.height)
+        .map(|_| read_color(reader))
+        .collect::<io::Result<_>>()?;
+    Ok((pixels, read_counters(reader)?))
+}
+
+// a connection to a coordinator, holding the job it sent
+pub struct Worker {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+    pub job: Job,
+}
+
+impl Worker {
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let writer = BufWriter::new(stream);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a render coordinator").into());
+        }
+        let scene = read_string(&mut reader)?;
+        let count = read_u64(&mut reader)?;
+        if count > MAX_ARGS {
+            return Err(invalid("too many arguments from coordinator").into());
+        }
+        let args = (0..count)
+            .map(|_| read_string(&mut reader))
+            .collect::<io::Result<_>>()?;
+        let count = read_u64(&mut reader)?;
+        if count > MAX_FILES {
+            return Err(invalid("too many files from coordinator").into());
+        }
+        let files = (0..count)
+            .map(|_| {
+                Ok((
+                    PathBuf::from(read_string(&mut reader)?),
+                    read_bytes(&mut reader, MAX_FILE)?,
+                ))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Worker {
+            reader,
+            writer,
+            job: Job { scene, args, files },
+        })
+    }
+
+    // renders tiles of the world through the camera, set up from the job, until the
+    // coordinator has none left; returns the number of tiles rendered
+    pub fn render(mut self, world: &World, mut camera: Camera) -> Result<usize> {
+        let (reader, writer) = (&mut self.reader, &mut self.writer);
+        let mut rendered = 0;
+        loop {
+            match read_u64(reader)? {
+                TILE => {}
+                DONE => return Ok(rendered),
+                _ => return Err(invalid("unknown message from coordinator").into()),
+            }
+            let tile = read_tile(reader)?;
+            let fits = |start: usize, size: usize, limit: f64| {
+                start
+                    .checked_add(size)
+                    .is_some_and(|end| end <= limit as usize)
+            };
+            if !fits(tile.x, tile.width, camera.hsize) || !fits(tile.y, tile.height, camera.vsize) {
+                return Err(invalid("tile lies outside the image").into());
+            }
+            camera.region = Some(tile);
+            let frame = camera.render_passes(world, &[])?;
+            let region = camera.region();
+            for n in [region.x, region.y, region.width, region.height] {
+                write_u64(writer, n as u64)?;
+            }
+            for color in frame.beauty.canvas.iter().flatten() {
+                for channel in [color.rgb.x, color.rgb.y, color.rgb.z] {
+                    write_u64(writer, channel.to_bits())?;
+                }
+            }
+            write_counters(writer, &frame.stats.counters)?;
+            writer.flush()?;
+            rendered += 1;
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_u64(writer: &mut impl Write, n: u64) -> io::Result<()> {
+    writer.write_all(&n.to_le_bytes())
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_u64(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes(reader: &mut impl Read, max: u64) -> io::Result<Vec<u8>> {
+    let len = read_u64(reader)?;
+    if len > max {
+        return Err(invalid("message too long"));
+    }
+    let mut bytes = vec![];
+    if reader.take(len).read_to_end(&mut bytes)? as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+fn write_string(writer: &mut impl Write, s: &str) -> io::Result<()> {
+    write_bytes(writer, s.as_bytes())
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader, MAX_STRING)?).map_err(|_| invalid("message is not text"))
+}
+
+fn read_tile(reader: &mut impl Read) -> io::Result<Tile> {
+    let mut read = || usize::try_from(read_u64(reader)?).map_err(|_| invalid("tile out of range"));
+    Ok(Tile {
+        x: read()?,
+        y: read()?,
+        width: read()?,
+        height: read()?,
+    })
+}
+
+fn read_color(reader: &mut impl Read) -> io::Result<Color> {
+    let [r, g, b] = [read_u64(reader)?, read_u64(reader)?, read_u64(reader)?].map(f64::from_bits);
+    Ok(Color::new(r, g, b))
+}
+
+fn write_counters(writer: &mut impl Write, counters: &Counters) -> io::Result<()> {
+    for n in [
+        counters.primary_rays,
+        counters.shadow_rays,
+        counters.secondary_rays,
+        counters.inversions,
+        counters.tests.len() as u64,
+    ] {
+        write_u64(writer, n)?;
+    }
+    for (kind, tests) in &counters.tests {
+        write_string(writer, kind)?;
+        write_u64(writer, *tests)?;
+    }
+    Ok(())
+}
+
+fn read_counters(reader: &mut impl Read) -> io::Result<Counters> {
+    let mut counters = Counters {
+        primary_rays: read_u64(reader)?,
+        shadow_rays: read_u64(reader)?,
+        secondary_rays: read_u64(reader)?,
+        inversions: read_u64(reader)?,
+        ..Default::default()
+    };
+    let kinds = read_u64(reader)?;
+    if kinds > MAX_ARGS {
+        return Err(invalid("too many kinds of shape"));
+    }
+    for _ in 0..kinds {
+        let kind = read_string(reader)?;
+        counters.tests.insert(kind.into(), read_u64(reader)?);
+    }
+    Ok(counters)
+}
+
+#[cfg(test)]
+mod farm_tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::features::{error::Error, scene::Scene};
+
+    const SCENE: &str = "
+        camera width=24 height=16 fov=60 from=0,1,-5 to=0,1,0 up=0,1,0 samples=2 integrator=path
+        light position=-10,10,-10 intensity=1,1,1
+        material red color=1,0,0 diffuse=0.7
+        plane
+        sphere material=red translate=0,1,0
+    ";
+
+    fn prepare(job: &Job) -> Result<Scene> {
+        let mut scene = Scene::parse(&job.scene)?;
+        scene.camera.tile_size = 8;
+        Ok(scene)
+    }
+
+    fn work(address: SocketAddr) -> Result<usize> {
+        let worker = Worker::connect(address)?;
+        let Scene { world, camera, .. } = prepare(&worker.job)?;
+        worker.render(&world, camera)
+    }
+
+    #[test]
+    fn workers_render_the_same_image() {
+        let coordinator = Coordinator::bind("127.0.0.1:0").unwrap();
+        let address = coordinator.local_addr().unwrap();
+        let job = Job {
+            scene: SCENE.to_string(),
+            args: vec![],
+            files: vec![],
+        };
+        let Scene { world, camera, .. } = prepare(&job).unwrap();
+        let workers: Vec<_> = (0..2)
+            .map(|_| thread::spawn(move || work(address).unwrap()))
+            .collect();
+        let tiles = Mutex::new(vec![]);
+        let frame = coordinator
+            .render(&job, &camera, &|event| {
+                if let RenderEvent::Tile { tile, .. } = event {
+                    tiles.lock().unwrap().push(*tile);
+                }
+            })
+            .unwrap();
+        let local = camera.render_passes(&world, &[]).unwrap();
+        assert_eq!(frame.beauty, local.beauty);
+        assert_eq!(frame.stats.counters.primary_rays, 24 * 16 * 2);
+        assert_eq!(frame.stats.counters.tests, local.stats.counters.tests);
+        let rendered: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+        assert_eq!(rendered, 6);
+        assert_eq!(tiles.into_inner().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn workers_load_the_files_sent_with_the_scene() {
+        let coordinator = Coordinator::bind("127.0.0.1:0").unwrap();
+        let address = coordinator.local_addr().unwrap();
+        // nothing is read from the coordinator's disk, which the worker may not share
+        let job = Job {
+            scene: format!(
+                "{}\npattern red texture mapping=planar uv=red.ppm\n\
+                 material textured pattern=red\nsphere material=textured translate=2,1,0",
+                SCENE
+            ),
+            args: vec![],
+            files: vec![(
+                PathBuf::from("/nowhere/red.ppm"),
+                b"P3\n1 1\n255\n255 0 0\n".to_vec(),
+            )],
+        };
+        let prepare = |job: &Job| {
+            let mut scene = Scene::parse_with_files(&job.scene, "/nowhere/scene.txt", &job.files)?;
+            scene.camera.tile_size = 8;
+            Ok::<_, Error>(scene)
+        };
+        let Scene { world, camera, .. } = prepare(&job).unwrap();
+        let worker = thread::spawn(move || {
+            let worker = Worker::connect(address).unwrap();
+            let Scene { world, camera, .. } = prepare(&worker.job).unwrap();
+            worker.render(&world, camera).unwrap()
+        });
+        let frame = coordinator.render(&job, &camera, &|_| {}).unwrap();
+        assert_eq!(frame.beauty, camera.render(&world).unwrap());
+        assert_eq!(worker.join().unwrap(), 6);
+    }
+
+    #[test]
+    fn tiles_of_dead_workers_go_to_others() {
+        let coordinator = Coordinator::bind("127.0.0.1:0").unwrap();
+        let address = coordinator.local_addr().unwrap();
+        let job = Job {
+            scene: SCENE.to_string(),
+            args: vec!["--samples".to_string(), "2".to_string()],
+            files: vec![],
+        };
+        let Scene { world, camera, .. } = prepare(&job).unwrap();
+        let (taken, started) = mpsc::channel();
+        let farm = thread::spawn(move || {
+            // takes a tile and hangs up without rendering it
+            let mut stream = TcpStream::connect(address).unwrap();
+            let mut magic = [0; 8];
+            stream.read_exact(&mut magic).unwrap();
+            assert_eq!(read_string(&mut stream).unwrap(), SCENE);
+            assert_eq!(read_u64(&mut stream).unwrap(), 2);
+            assert_eq!(read_string(&mut stream).unwrap(), "--samples");
+            assert_eq!(read_string(&mut stream).unwrap(), "2");
+            assert_eq!(read_u64(&mut stream).unwrap(), 0);
+            assert_eq!(read_u64(&mut stream).unwrap(), TILE);
+            read_tile(&mut stream).unwrap();
+            drop(stream);
+            taken.send(()).unwrap();
+            work(address).unwrap()
+        });
+        let frame = coordinator.render(&job, &camera, &|_| {}).unwrap();
+        started.recv().unwrap();
+        assert_eq!(frame.beauty, camera.render(&world).unwrap());
+        assert_eq!(farm.join().unwrap(), 6);
+    }
+
+    #[test]
+    fn renders_without_workers_give_up() {
+        let mut coordinator = Coordinator::bind("127.0.0.1:0").unwrap();
+        coordinator.timeout = Duration::from_millis(100);
+        let job = Job {
+            scene: SCENE.to_string(),
+            args: vec![],
+            files: vec![],
+        };
+        let Scene { camera, .. } = prepare(&job).unwrap();
+        let err = coordinator.render(&job, &camera, &|_| {}).err().unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("no worker finished a tile in 100ms, 6 of 6"));
+    }
+
+    #[test]
+    fn workers_refuse_what_they_cannot_render() {
+        let mut long = (MAX_STRING + 1).to_le_bytes().to_vec();
+        long.extend(b"scene");
+        assert!(read_string(&mut long.as_slice()).is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let worker = thread::spawn(move || work(address));
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(MAGIC).unwrap();
+        write_string(&mut stream, SCENE).unwrap();
+        for n in [0, 0, TILE, 0, 0, 1 << 40, 1] {
+            write_u64(&mut stream, n).unwrap();
+        }
+        let err = worker.join().unwrap().err().unwrap();
+        assert_eq!(err.to_string(), "tile lies outside the image");
+    }
+}
@@ -0,0 +1,44 @@
+use super::color::Color;
+
+// exponential depth fog: the further light travels the more of it is swapped for the fog color
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Fog {
+    pub color: Color,
+    // the fraction of light lost per unit of distance travelled
+    pub density: f64,
+}
+
+impl Fog {
+    pub fn new(color: Color, density: f64) -> Self {
+        Fog { color, density }
+    }
+
+    // the share of light that gets through unscattered, rays that miss everything travel forever
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        if self.density <= 0.0 {
+            return 1.0;
+        }
+        (-self.density * distance).exp()
+    }
+
+    pub fn apply(&self, color: Color, distance: f64) -> Color {
+        let t = self.transmittance(distance);
+        color * t + self.color * (1.0 - t)
+    }
+}
+
+#[cfg(test)]
+mod fog_tests {
+    use super::*;
+    use crate::features::consts::{BLACK, WHITE};
+
+    #[test]
+    fn fog_thickens_with_distance() {
+        let fog = Fog::new(WHITE, 0.5);
+        assert_eq!(fog.apply(BLACK, 0.0), BLACK);
+        let t = (-1.0_f64).exp();
+        assert_eq!(fog.apply(BLACK, 2.0), Color::new(1.0 - t, 1.0 - t, 1.0 - t));
+        assert_eq!(fog.apply(BLACK, f64::INFINITY), WHITE);
+        assert_eq!(Fog::new(WHITE, 0.0).apply(BLACK, f64::INFINITY), BLACK);
+    }
+}
@@ -0,0 +1,310 @@
+use std::f64::consts::PI;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    color::Color,
+    computation::Computation,
+    consts::{BLACK, WHITE},
+    error::Result,
+    intersection::hit,
+    occlusion::AmbientOcclusion,
+    ray::Ray,
+    shape::Shape,
+    stats,
+    vector::Vector,
+    world::World,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Integrator {
+    // direct light from the lights plus a constant ambient term
+    #[default]
+    Whitted,
+    PathTracer(PathTracer),
+    // grayscale ambient occlusion of whatever the camera sees
+    AmbientOcclusion(AmbientOcclusion),
+}
+
+impl Integrator {
+    pub fn radiance<R: Rng>(&self, world: &World, ray: &Ray, rng: &mut R) -> Result<Color> {
+        match self {
+            Integrator::Whitted => world.color_at_with(ray, rng),
+            Integrator::PathTracer(tracer) => tracer.radiance(world, ray, rng),
+            Integrator::AmbientOcclusion(ao) => ao.radiance(world, ray, rng),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PathTracer {
+    pub max_depth: usize,
+    // bounces always traced before russian roulette may end a path
+    pub roulette_depth: usize,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: usize, roulette_depth: usize) -> Self {
+        PathTracer {
+            max_depth,
+            roulette_depth,
+        }
+    }
+
+    // estimates the light travelling back along the ray: direct light is sampled at every
+    // vertex (next-event estimation), indirect light by cosine weighted matte bounces
+    pub fn radiance<R: Rng>(&self, world: &World, ray: &Ray, rng: &mut R) -> Result<Color> {
+        let mut color = BLACK;
+        let mut throughput = WHITE;
+        let mut ray = *ray;
+        for depth in 0..self.max_depth {
+            let hit = match world.intersect(&ray).and_then(hit) {
+                Some(hit) => hit,
+                // paths that escape pick up the background, lighting the scene by it
+                None => {
+                    let background = world.background.at(&ray.direction);
+                    color = color + throughput * world.fogged(background, f64::INFINITY);
+                    break;
+                }
+            };
+            // fog swaps part of what lies beyond for its own color on every segment
+            if let Some(fog) = &world.fog {
+                let t = fog.transmittance(hit.t * ray.direction.magnitude());
+                color = color + throughput * fog.color * (1.0 - t);
+                throughput = throughput * t;
+            }
+            let shape = &world.get_shape_at(&hit.shape_id, ray.time)?;
+            let comps = Computation::new(&ray, &hit, shape);
+            let material = shape.get_material();
+            // sampled emitters were already counted by the previous vertex
+            if depth == 0 || !world.get_emitters().contains(&hit.shape_id) {
+                color = color + throughput * material.emitted(shape, &comps.point);
+            }
+            color = color + throughput * world.direct_light(shape, &material, &comps, rng)?;
+
+            throughput = throughput * material.albedo(shape, &comps.point);
+            if depth + 1 >= self.roulette_depth {
+                let rgb = throughput.rgb;
+                let survival = rgb.x.max(rgb.y).max(rgb.z).clamp(0.05, 0.95);
+                if rng.gen::<f64>() >= survival {
+                    break;
+                }
+                throughput = throughput * (1.0 / survival);
+            }
+            let direction = if shape.is_volume() {
+                uniform_sphere(rng)
+            } else {
+                cosine_hemisphere(&comps.normalv, rng)
+            };
+            ray = Ray::new(comps.over_point, direction).with_time(ray.time);
+            stats::count(|c| c.secondary_rays += 1);
+        }
+        Ok(color)
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        PathTracer::new(16, 3)
+    }
+}
+
+// the stream of one sample of one pixel, hashed from the seed, the pixel and the
+// sample's index, so renders repeat bit for bit whatever the thread count or the order
+// the tiles are taken in
+pub(crate) fn sample_rng(seed: u64, px: usize, py: usize, sample: usize) -> StdRng {
+    let pixel = mix(((py as u64) << 32) | px as u64);
+    StdRng::seed_from_u64(mix(seed ^ mix(pixel ^ mix(sample as u64))))
+}
+
+// splitmix64 finalizer, spreading nearby seeds far apart
+pub(crate) fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// directions around the normal with density proportional to the cosine, which
+// cancels the cosine and the 1/pi of a matte surface from the estimate
+pub(crate) fn cosine_hemisphere<R: Rng>(normal: &Vector, rng: &mut R) -> Vector {
+    let helper = if normal.position.x.abs() > 0.9 {
+        Vector::new(0.0, 1.0, 0.0)
+    } else {
+        Vector::new(1.0, 0.0, 0.0)
+    };
+    let tangent = normal.cross_product(&helper).normalize();
+    let bitangent = normal.cross_product(&tangent);
+    let (u, v): (f64, f64) = (rng.gen(), rng.gen());
+    let r = u.sqrt();
+    let phi = 2.0 * PI * v;
+    (tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + *normal * (1.0 - u).sqrt())
+        .normalize()
+}
+
+// particles scatter evenly in every direction, which with the albedo already in the
+// throughput leaves nothing else to weight
+fn uniform_sphere<R: Rng>(rng: &mut R) -> Vector {
+    let z = 1.0 - 2.0 * rng.gen::<f64>();
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * rng.gen::<f64>();
+    Vector::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+#[cfg(test)]
+mod path_tracer_tests {
+    use super::*;
+    use crate::features::{
+        background::Background,
+        light::Light,
+        material::Material,
+        point::Point,
+        shape::{plane::Plane, sphere::Sphere, Shape},
+        transformations::{scaling, translation},
+    };
+
+    #[test]
+    fn cosine_hemisphere_stays_above_the_surface() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let normal = Vector::new(0.0, 0.6, 0.8);
+        let mut mean_cosine = 0.0;
+        for _ in 0..2000 {
+            let d = cosine_hemisphere(&normal, &mut rng);
+            let cosine = d.dot_product(&normal);
+            assert!(cosine >= 0.0);
+            assert!((d.magnitude() - 1.0).abs() < 1e-9);
+            mean_cosine += cosine / 2000.0;
+        }
+        // the mean cosine of a cosine weighted hemisphere is 2/3
+        assert!((mean_cosine - 2.0 / 3.0).abs() < 0.02, "{}", mean_cosine);
+    }
+
+    #[test]
+    fn same_seed_same_estimate() {
+        let world = World::default();
+        let tracer = PathTracer::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let a = tracer
+            .radiance(&world, &ray, &mut StdRng::seed_from_u64(34))
+            .unwrap();
+        let b = tracer
+            .radiance(&world, &ray, &mut StdRng::seed_from_u64(34))
+            .unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn background_lights_the_scene() {
+        // a white floor under a uniform sky reflects the sky back up
+        let mut world = World::new(Light::new(Point::new(0.0, 10.0, 0.0), BLACK));
+        world.background = Background::Solid(Color::new(0.5, 0.5, 0.5));
+        let floor = Material {
+            diffuse: 1.0,
+            ..Default::default()
+        };
+        let mut plane = Plane::default();
+        plane.set_material(floor).unwrap();
+        world.add_shape(Box::new(plane));
+        let tracer = PathTracer::default();
+        let ray = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let c = tracer
+            .radiance(&world, &ray, &mut StdRng::seed_from_u64(1))
+            .unwrap();
+        assert_eq!(c, Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn fog_glows_along_every_segment() {
+        use crate::features::fog::Fog;
+        let mut world = World::new(Light::new(Point::new(0.0, 10.0, 0.0), BLACK));
+        let fog = Fog::new(Color::new(0.4, 0.4, 0.4), 0.5);
+        world.fog = Some(fog);
+        let tracer = PathTracer::default();
+        let mut rng = StdRng::seed_from_u64(2);
+        let up = Ray::new(Point::new(0.0, 2.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(tracer.radiance(&world, &up, &mut rng).unwrap(), fog.color);
+        // a black floor two units away is seen through two units of fog
+        let mut plane = Plane::default();
+        plane
+            .set_material(Material {
+                color: BLACK,
+                ambient: 0.0,
+                ..Default::default()
+            })
+            .unwrap();
+        world.add_shape(Box::new(plane));
+        let down = Ray::new(Point::new(0.0, 2.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(
+            tracer.radiance(&world, &down, &mut rng).unwrap(),
+            fog.apply(BLACK, 2.0)
+        );
+    }
+
+    #[test]
+    fn misses_are_black() {
+        let world = World::default();
+        let tracer = PathTracer::default();
+        let ray = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        let c = tracer
+            .radiance(&world, &ray, &mut StdRng::seed_from_u64(0))
+            .unwrap();
+        assert_eq!(c, BLACK);
+    }
+
+    #[test]
+    fn furnace_converges_to_the_geometric_series() {
+        // between two glowing planes of albedo a every path sees emission e at each
+        // bounce, so the radiance is e / (1 - a)
+        let mut world = World::new(Light::new(Point::new(0.0, 0.0, 0.0), BLACK));
+        let material = Material {
+            color: WHITE,
+            diffuse: 0.5,
+            emission: Color::new(1.0, 1.0, 1.0),
+            ..Default::default()
+        };
+        // a plane can't be sampled as a light, so all emission is found by bouncing
+        let mut floor = Plane::default();
+        floor.set_material(material.clone()).unwrap();
+        let mut ceiling = Plane::default();
+        ceiling.set_material(material).unwrap();
+        ceiling.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        world.add_shape(Box::new(floor));
+        world.add_shape(Box::new(ceiling));
+        let tracer = PathTracer::new(64, 3);
+        let mut rng = StdRng::seed_from_u64(0);
+        let ray = Ray::new(Point::new(0.0, 0.5, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let mut total = 0.0;
+        for _ in 0..4000 {
+            total += tracer.radiance(&world, &ray, &mut rng).unwrap().rgb.x;
+        }
+        let estimate = total / 4000.0;
+        assert!((estimate - 2.0).abs() < 0.1, "{}", estimate);
+    }
+
+    #[test]
+    fn indirect_light_reaches_shadows() {
+        // a floor shadowed from the light still gets light bounced between the lit floor
+        // around it and the underside of the blocker
+        let mut world = World::new(Light::new(Point::new(0.0, 10.0, 0.0), WHITE));
+        world.add_shape(Box::<Plane>::default());
+        let mut blocker = Sphere::default();
+        blocker
+            .set_transform(translation(0.0, 1.0, 0.0) * scaling(2.0, 0.1, 2.0))
+            .unwrap();
+        world.add_shape(Box::new(blocker));
+        let ray = Ray::new(Point::new(0.0, 0.5, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let tracer = PathTracer::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let direct = world.color_at(&ray).unwrap();
+        let mut total = 0.0;
+        for _ in 0..200 {
+            total += tracer.radiance(&world, &ray, &mut rng).unwrap().rgb.x;
+        }
+        // whitted only has its ambient term here
+        assert_eq!(direct, Color::new(0.1, 0.1, 0.1));
+        // the blocker fills most of the view from the shadowed point and its underside
+        // is lit by the floor around it, which is far more than the ambient term
+        let estimate = total / 200.0;
+        assert!(estimate > 0.15 && estimate < 0.3, "{}", estimate);
+    }
+}
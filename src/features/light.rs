@@ -1,9 +1,18 @@
-use super::{color::Color, point::Point};
+use super::{color::Color, consts::BLACK, point::Point, vector::Vector};
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Light {
     pub position: Point,
     pub intensity: Color,
+    pub spot: Option<Spot>,
+}
+
+// narrows a light to a cone, fading out between the inner and outer angles
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Spot {
+    pub direction: Vector,
+    pub cos_inner: f64,
+    pub cos_outer: f64,
 }
 
 impl Light {
@@ -11,8 +20,48 @@ impl Light {
         Light {
             position,
             intensity,
+            spot: None,
+        }
+    }
+
+    // angles are measured from the direction to the edge of the cone, in radians
+    pub fn spot(
+        position: Point,
+        intensity: Color,
+        direction: Vector,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> Self {
+        let outer_angle = outer_angle.max(inner_angle);
+        Light {
+            position,
+            intensity,
+            spot: Some(Spot {
+                direction: direction.normalize(),
+                cos_inner: inner_angle.cos(),
+                cos_outer: outer_angle.cos(),
+            }),
         }
     }
+
+    // the intensity the light shines with towards a point
+    pub fn intensity_at(&self, point: &Point) -> Color {
+        let spot = match &self.spot {
+            Some(spot) => spot,
+            None => return self.intensity,
+        };
+        let cosine = (*point - self.position)
+            .normalize()
+            .dot_product(&spot.direction);
+        if cosine >= spot.cos_inner {
+            return self.intensity;
+        }
+        if cosine <= spot.cos_outer {
+            return BLACK;
+        }
+        let t = (cosine - spot.cos_outer) / (spot.cos_inner - spot.cos_outer);
+        self.intensity * (t * t * (3.0 - 2.0 * t))
+    }
 }
 
 #[cfg(test)]
@@ -28,4 +77,28 @@ mod light_tests {
         assert_eq!(light.intensity, intensity);
         assert_eq!(light.position, position);
     }
+
+    #[test]
+    fn spot_lights_fade_out_across_the_cone_edge() {
+        use std::f64::consts::PI;
+        let light = Light::spot(
+            Point::new(0.0, 10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            Vector::new(0.0, -1.0, 0.0),
+            PI / 6.0,
+            PI / 4.0,
+        );
+        assert_eq!(
+            light.intensity_at(&Point::new(1.0, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(light.intensity_at(&Point::new(20.0, 0.0, 0.0)), BLACK);
+        let edge = light.intensity_at(&Point::new(7.0, 0.0, 0.0)).rgb.x;
+        assert!(edge > 0.0 && edge < 1.0, "{}", edge);
+        assert_eq!(
+            Light::new(Point::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0))
+                .intensity_at(&Point::new(20.0, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
 }
@@ -1,13 +1,23 @@
 use super::{
     color::Color,
     consts::{BLACK, WHITE},
+    error::{Error, Result},
     light::Light,
+    normal::NormalModifier,
     pattern::Pattern,
+    pbr::CookTorrance,
     point::Point,
     shape::Shape,
     vector::Vector,
 };
 
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum Model {
+    #[default]
+    Phong,
+    CookTorrance(Box<CookTorrance>),
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Material {
     pub color: Color,
@@ -16,6 +26,10 @@ pub struct Material {
     pub specular: f64,
     pub shininess: f64,
     pub pattern: Option<Pattern>,
+    pub normal: Option<NormalModifier>,
+    pub model: Model,
+    pub emission: Color,
+    pub emission_strength: f64,
 }
 
 impl Material {
@@ -34,49 +48,122 @@ impl Material {
             specular,
             shininess,
             pattern,
+            normal: None,
+            model: Model::Phong,
+            emission: BLACK,
+            emission_strength: 1.0,
         }
     }
-    pub fn lighting(
+    pub fn validate(&self) -> Result<()> {
+        for (name, color) in [("color", self.color), ("emission", self.emission)] {
+            let rgb = color.rgb;
+            if [rgb.x, rgb.y, rgb.z]
+                .iter()
+                .any(|c| !c.is_finite() || *c < 0.0)
+            {
+                return Err(Error::InvalidMaterial(format!(
+                    "{} components must be finite and non-negative, found {:?}",
+                    name, rgb
+                )));
+            }
+        }
+        for (name, value) in [
+            ("ambient", self.ambient),
+            ("diffuse", self.diffuse),
+            ("specular", self.specular),
+            ("emission strength", self.emission_strength),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(Error::InvalidMaterial(format!(
+                    "{} must be finite and non-negative, found {}",
+                    name, value
+                )));
+            }
+        }
+        if !self.shininess.is_finite() || self.shininess <= 0.0 {
+            return Err(Error::InvalidMaterial(format!(
+                "shininess must be positive, found {}",
+                self.shininess
+            )));
+        }
+        Ok(())
+    }
+    pub fn is_emissive(&self) -> bool {
+        let glows = self.emission * self.emission_strength != BLACK;
+        match &self.model {
+            Model::Phong => glows,
+            Model::CookTorrance(pbr) => glows || pbr.emissive != Pattern::from(BLACK),
+        }
+    }
+    pub fn emitted(&self, object: &dyn Shape, point: &Point) -> Color {
+        let glow = self.emission * self.emission_strength;
+        match &self.model {
+            Model::Phong => glow,
+            Model::CookTorrance(pbr) => glow + pbr.emitted(object, point),
+        }
+    }
+    pub fn ambient(&self, light: &Light, object: &dyn Shape, point: &Point) -> Color {
+        let color = match &self.model {
+            Model::Phong => self.color_at(object, point),
+            Model::CookTorrance(pbr) => pbr.albedo(object, point),
+        };
+        color * light.intensity * self.ambient
+    }
+    // the share of light a matte bounce keeps, used when tracing indirect light
+    pub fn albedo(&self, object: &dyn Shape, point: &Point) -> Color {
+        match &self.model {
+            Model::Phong => self.color_at(object, point) * self.diffuse,
+            Model::CookTorrance(pbr) => pbr.albedo(object, point),
+        }
+    }
+    // light reflected straight from the light towards the eye, ignoring shadows
+    pub fn direct(
         &self,
         light: &Light,
         object: &dyn Shape,
         point: &Point,
         eyev: &Vector,
         normalv: &Vector,
-        in_shadow: bool,
     ) -> Color {
-        let color = match &self.pattern {
-            Some(p) => match p.at_obj(object, point) {
-                Some(c) => c,
-                None => self.color,
-            },
-            None => self.color,
-        };
-
-        let effective_color = color * light.intensity;
+        if let Model::CookTorrance(pbr) = &self.model {
+            return pbr.direct(light, object, point, eyev, normalv);
+        }
+        let intensity = light.intensity_at(point);
+        let effective_color = self.color_at(object, point) * intensity;
         let lightv = (light.position - *point).normalize();
-        let ambient = effective_color * self.ambient;
         let light_dot_normal = lightv.dot_product(normalv);
-        let diffuse: Color;
-        let specular: Color;
         if light_dot_normal < 0.0 {
-            diffuse = BLACK;
-            specular = BLACK;
-        } else {
-            diffuse = effective_color * self.diffuse * light_dot_normal;
-            let reflectv = (-lightv).reflect(normalv);
-            let reflectv_dot_eye = reflectv.dot_product(eyev);
-            if reflectv_dot_eye <= 0.0 {
-                specular = BLACK;
-            } else {
-                let factor = reflectv_dot_eye.powf(self.shininess);
-                specular = light.intensity * self.specular * factor;
-            }
+            return BLACK;
         }
+        let diffuse = effective_color * self.diffuse * light_dot_normal;
+        let reflectv = (-lightv).reflect(normalv);
+        let reflectv_dot_eye = reflectv.dot_product(eyev);
+        if reflectv_dot_eye <= 0.0 {
+            return diffuse;
+        }
+        let factor = reflectv_dot_eye.powf(self.shininess);
+        diffuse + intensity * self.specular * factor
+    }
+    pub fn lighting(
+        &self,
+        light: &Light,
+        object: &dyn Shape,
+        point: &Point,
+        eyev: &Vector,
+        normalv: &Vector,
+        in_shadow: bool,
+    ) -> Color {
+        let unlit = self.emitted(object, point) + self.ambient(light, object, point);
         if in_shadow {
-            return ambient;
+            return unlit;
+        }
+        unlit + self.direct(light, object, point, eyev, normalv)
+    }
+    fn color_at(&self, object: &dyn Shape, point: &Point) -> Color {
+        match &self.pattern {
+            Some(p) => p.at_obj(object, point),
+            None => self.color,
         }
-        ambient + diffuse + specular
     }
 }
 
@@ -99,6 +186,24 @@ mod material_tests {
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
     }
+
+    #[test]
+    fn validate_rejects_negative_coefficients() {
+        let m = Material {
+            diffuse: -0.5,
+            ..Default::default()
+        };
+        assert!(matches!(m.validate(), Err(Error::InvalidMaterial(_))));
+    }
+
+    #[test]
+    fn validate_rejects_zero_shininess() {
+        let m = Material {
+            shininess: 0.0,
+            ..Default::default()
+        };
+        assert!(matches!(m.validate(), Err(Error::InvalidMaterial(_))));
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,12 @@
 use std::ops::Mul;
 
-use super::{point::Point, tuple::Tuple, vector::Vector};
+use super::{
+    error::{Error, Result},
+    point::Point,
+    stats,
+    tuple::Tuple,
+    vector::Vector,
+};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Matrice {
@@ -16,10 +22,11 @@ impl Matrice {
         }
     }
 
-    pub fn inverse(&self) -> Option<Self> {
+    pub fn inverse(&self) -> Result<Self> {
+        stats::count(|c| c.inversions += 1);
         let det = self.determinant();
         if det == 0.0 {
-            return None;
+            return Err(Error::SingularTransform(self.clone()));
         }
         let mut out = Self::new(self.size);
         let mut c;
@@ -29,7 +36,7 @@ impl Matrice {
                 out.write_element(col, row, c / det);
             }
         }
-        Some(out)
+        Ok(out)
     }
     pub fn determinant(&self) -> f64 {
         if self.size == 2 {
@@ -151,3 +158,22 @@ impl Mul<Point> for Matrice {
         Point::from(Tuple::new(out[0], out[1], out[2]))
     }
 }
+
+#[cfg(test)]
+mod inverse_tests {
+    use crate::features::transformations::{scaling, translation};
+
+    use super::*;
+
+    #[test]
+    fn inverse_of_translation() {
+        let m = translation(5.0, -3.0, 2.0);
+        assert_eq!(m.inverse().unwrap(), translation(-5.0, 3.0, -2.0));
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        let m = scaling(5.0, 0.0, 0.0);
+        assert!(matches!(m.inverse(), Err(Error::SingularTransform(_))));
+    }
+}
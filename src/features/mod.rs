@@ -1,16 +1,34 @@
+pub mod animation;
+pub mod aov;
+pub mod background;
 pub mod camera;
 pub mod canvas;
+pub mod checkpoint;
 pub mod color;
 pub mod computation;
 pub mod consts;
+pub mod control;
+pub mod error;
+pub mod farm;
+pub mod fog;
+pub mod integrator;
 pub mod intersection;
 pub mod light;
 pub mod material;
 pub mod matrice;
+pub mod motion;
+pub mod noise;
+pub mod normal;
+pub mod occlusion;
 pub mod pattern;
+pub mod pbr;
 pub mod point;
+pub mod progress;
 pub mod ray;
+pub mod scene;
 pub mod shape;
+pub mod stats;
+pub mod tiles;
 pub mod transformations;
 pub mod tuple;
 pub mod vector;
@@ -0,0 +1,344 @@
+use super::{
+    error::{Error, Result},
+    material::Material,
+    matrice::Matrice,
+    point::Point,
+    ray::Ray,
+    shape::Shape,
+    transformations::translation,
+    tuple::Tuple,
+    vector::Vector,
+};
+
+// a shape's transform while the shutter is open, moving from its own transform at
+// time 0 to an end transform at time 1. Both ends are split into a translation, a
+// rotation and a stretch holding scale and shear, which are blended separately so the
+// shape turns instead of squashing on its way
+#[derive(Debug, Clone, PartialEq)]
+pub struct Motion {
+    end: Matrice,
+    from: Pose,
+    to: Pose,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Pose {
+    translation: Tuple,
+    // a unit quaternion, w first
+    rotation: [f64; 4],
+    // a symmetric matrix, with no translation
+    stretch: Matrice,
+}
+
+impl Motion {
+    pub fn new(start: &Matrice, end: &Matrice) -> Result<Self> {
+        // a shape turning inside out on its way would be flat somewhere in between
+        let mirrored = |m: &Matrice| m.submatrix(3, 3).determinant() < 0.0;
+        if mirrored(start) != mirrored(end) {
+            return Err(Error::SingularTransform(end.clone()));
+        }
+        Ok(Motion {
+            end: end.clone(),
+            from: Pose::of(start)?,
+            to: Pose::of(end)?,
+        })
+    }
+    pub fn get_end(&self) -> &Matrice {
+        &self.end
+    }
+    pub fn transform_at(&self, time: f64) -> Matrice {
+        let (t, r, stretch) = self.blend(time);
+        translation(t.x, t.y, t.z) * rotation(r) * stretch
+    }
+    // the parts are inverted one by one, the rotation and translation by flipping them
+    pub fn inverse_at(&self, time: f64) -> Matrice {
+        let (t, [w, x, y, z], stretch) = self.blend(time);
+        // blends of stretches that keep or flip handedness alike never flatten
+        let stretch = stretch
+            .inverse()
+            .expect("stretches of one handedness blend to invertible ones");
+        stretch * rotation([w, -x, -y, -z]) * translation(-t.x, -t.y, -t.z)
+    }
+
+    fn blend(&self, time: f64) -> (Tuple, [f64; 4], Matrice) {
+        let time = time.clamp(0.0, 1.0);
+        let (from, to) = (&self.from, &self.to);
+        let mut stretch = Matrice::identity_matrix(4);
+        for row in 0..3 {
+            for column in 0..3 {
+                let (a, b) = (
+                    from.stretch.element_at(row, column),
+                    to.stretch.element_at(row, column),
+                );
+                stretch.write_element(row, column, a + (b - a) * time);
+            }
+        }
+        (
+            from.translation + (to.translation - from.translation) * time,
+            slerp(from.rotation, to.rotation, time),
+            stretch,
+        )
+    }
+}
+
+impl Pose {
+    // a polar decomposition of the transform, which rebuilds it exactly whatever its
+    // scale and shear
+    fn of(m: &Matrice) -> Result<Self> {
+        let mut linear = m.clone();
+        for row in 0..3 {
+            linear.write_element(row, 3, 0.0);
+        }
+        // a mirroring transform is split into a proper rotation and a stretch that flips
+        let sign = if m.submatrix(3, 3).determinant() < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let mut turn = linear.clone();
+        for row in 0..3 {
+            for column in 0..3 {
+                turn.write_element(row, column, sign * linear.element_at(row, column));
+            }
+        }
+        // averaging a matrix with its inverse transpose converges on its rotation
+        for _ in 0..64 {
+            let other = turn.inverse()?.transpose();
+            let mut change: f64 = 0.0;
+            for row in 0..3 {
+                for column in 0..3 {
+                    let (a, b) = (turn.element_at(row, column), other.element_at(row, column));
+                    turn.write_element(row, column, (a + b) / 2.0);
+                    change = change.max((a - b).abs());
+                }
+            }
+            if change < 1e-12 {
+                break;
+            }
+        }
+        let e = |row, column| m.element_at(row, column);
+        Ok(Pose {
+            translation: Tuple::new(e(0, 3), e(1, 3), e(2, 3)),
+            rotation: quaternion(|row, column| turn.element_at(row, column)),
+            stretch: turn.transpose() * linear,
+        })
+    }
+}
+
+fn quaternion(r: impl Fn(usize, usize) -> f64) -> [f64; 4] {
+    let trace = r(0, 0) + r(1, 1) + r(2, 2);
+    let q = if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [
+            0.25 * s,
+            (r(2, 1) - r(1, 2)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+            (r(1, 0) - r(0, 1)) / s,
+        ]
+    } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+        let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+        [
+            (r(2, 1) - r(1, 2)) / s,
+            0.25 * s,
+            (r(0, 1) + r(1, 0)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+        ]
+    } else if r(1, 1) > r(2, 2) {
+        let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+        [
+            (r(0, 2) - r(2, 0)) / s,
+            (r(0, 1) + r(1, 0)) / s,
+            0.25 * s,
+            (r(1, 2) + r(2, 1)) / s,
+        ]
+    } else {
+        let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+        [
+            (r(1, 0) - r(0, 1)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+            (r(1, 2) + r(2, 1)) / s,
+            0.25 * s,
+        ]
+    };
+    normalize(q)
+}
+
+fn rotation([w, x, y, z]: [f64; 4]) -> Matrice {
+    let rows = [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ];
+    let mut m = Matrice::identity_matrix(4);
+    for (row, values) in rows.iter().enumerate() {
+        for (column, value) in values.iter().enumerate() {
+            m.write_element(row, column, *value);
+        }
+    }
+    m
+}
+
+// the shortest turn from one rotation to the other at an even angular speed
+fn slerp(a: [f64; 4], mut b: [f64; 4], time: f64) -> [f64; 4] {
+    let mut cos = (0..4).map(|i| a[i] * b[i]).sum::<f64>();
+    if cos < 0.0 {
+        b = b.map(|v| -v);
+        cos = -cos;
+    }
+    let (wa, wb) = if cos > 0.9995 {
+        (1.0 - time, time)
+    } else {
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (
+            ((1.0 - time) * angle).sin() / sin,
+            (time * angle).sin() / sin,
+        )
+    };
+    normalize([0, 1, 2, 3].map(|i| a[i] * wa + b[i] * wb))
+}
+
+fn normalize(q: [f64; 4]) -> [f64; 4] {
+    let length = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+    q.map(|v| v / length)
+}
+
+// a shape fixed where it stands at one time of the shutter interval, for shading
+// what a ray of that time hit. Still shapes stand in for themselves
+pub struct Posed<'a> {
+    shape: &'a dyn Shape,
+    pose: Option<(Matrice, Matrice)>,
+}
+
+impl<'a> Posed<'a> {
+    pub fn new(shape: &'a dyn Shape, time: f64) -> Self {
+        let pose = shape
+            .get_motion()
+            .map(|motion| (motion.transform_at(time), motion.inverse_at(time)));
+        Posed { shape, pose }
+    }
+}
+
+impl Shape for Posed<'_> {
+    // the shape itself already intersects rays where they find it
+    fn intersect(&self, r: &Ray) -> Option<Vec<f64>> {
+        self.shape.intersect(r)
+    }
+    fn local_intersect(&self, r: &Ray) -> Option<Vec<f64>> {
+        self.shape.local_intersect(r)
+    }
+    fn kind(&self) -> &'static str {
+        self.shape.kind()
+    }
+    fn set_transform(&mut self, _transform: Matrice) -> Result<()> {
+        unreachable!("posed shapes are only shaded")
+    }
+    fn set_material(&mut self, _material: Material) -> Result<()> {
+        unreachable!("posed shapes are only shaded")
+    }
+    fn set_motion(&mut self, _end: Option<Matrice>) -> Result<()> {
+        unreachable!("posed shapes are only shaded")
+    }
+    fn get_material(&self) -> Material {
+        self.shape.get_material()
+    }
+    fn get_transform(&self) -> Matrice {
+        match &self.pose {
+            Some((transform, _)) => transform.clone(),
+            None => self.shape.get_transform(),
+        }
+    }
+    fn get_inverse(&self) -> &Matrice {
+        match &self.pose {
+            Some((_, inverse)) => inverse,
+            None => self.shape.get_inverse(),
+        }
+    }
+    fn get_motion(&self) -> Option<&Motion> {
+        None
+    }
+    fn local_normal_at(&self, local_point: Point) -> Vector {
+        self.shape.local_normal_at(local_point)
+    }
+    fn local_sample(&self, u: f64, v: f64) -> Option<(Point, Vector, f64)> {
+        self.shape.local_sample(u, v)
+    }
+    fn is_volume(&self) -> bool {
+        self.shape.is_volume()
+    }
+    fn get_shape_id(&self) -> String {
+        self.shape.get_shape_id()
+    }
+}
+
+#[cfg(test)]
+mod motion_tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::features::{
+        consts::EPSILON,
+        transformations::{rotation_x, rotation_y, rotation_z, scaling, shearing},
+    };
+
+    fn assert_close(a: &Matrice, b: &Matrice) {
+        for row in 0..4 {
+            for column in 0..4 {
+                let (x, y) = (a.element_at(row, column), b.element_at(row, column));
+                assert!((x - y).abs() < EPSILON, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn motions_hold_both_ends() {
+        let start = translation(1.0, 2.0, 3.0) * rotation_y(0.3) * scaling(2.0, 1.0, 1.0);
+        let end = translation(-1.0, 0.0, 5.0) * rotation_z(1.2) * scaling(1.0, 3.0, 0.5);
+        let motion = Motion::new(&start, &end).unwrap();
+        assert_close(&motion.transform_at(0.0), &start);
+        assert_close(&motion.transform_at(1.0), &end);
+        assert_close(&motion.inverse_at(0.0), &start.inverse().unwrap());
+        assert_close(&motion.inverse_at(1.0), &end.inverse().unwrap());
+    }
+
+    #[test]
+    fn motions_keep_shear_and_any_order_of_parts() {
+        // scaled after turning, and sheared, neither splits into a plain scale
+        let start = rotation_x(0.4) * scaling(1.0, 3.0, 1.0) * rotation_z(0.7);
+        let end = translation(0.0, 2.0, 0.0) * shearing(1.0, 0.0, 0.5, 0.0, 0.0, 0.2);
+        let motion = Motion::new(&start, &end).unwrap();
+        assert_close(&motion.transform_at(0.0), &start);
+        assert_close(&motion.transform_at(1.0), &end);
+        let halfway = motion.transform_at(0.5);
+        assert_close(&motion.inverse_at(0.5), &halfway.inverse().unwrap());
+        // mirrored at both ends is fine, turning inside out on the way is not
+        let mirror = scaling(1.0, 1.0, -1.0);
+        assert!(Motion::new(&mirror, &(translation(1.0, 0.0, 0.0) * mirror.clone())).is_ok());
+        assert!(Motion::new(&Matrice::identity_matrix(4), &mirror).is_err());
+    }
+
+    #[test]
+    fn motions_turn_rigidly_halfway() {
+        let motion = Motion::new(&Matrice::identity_matrix(4), &rotation_z(PI / 2.0)).unwrap();
+        let halfway = motion.transform_at(0.5) * Point::new(1.0, 0.0, 0.0);
+        let diagonal = (0.5f64).sqrt();
+        assert_eq!(halfway, Point::new(diagonal, diagonal, 0.0));
+        let slid = Motion::new(&translation(0.0, 0.0, 0.0), &translation(4.0, 0.0, 0.0)).unwrap();
+        assert_eq!(
+            slid.transform_at(0.25) * Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0)
+        );
+    }
+}
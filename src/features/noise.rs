@@ -0,0 +1,150 @@
+use super::point::Point;
+
+// Ken Perlin's reference permutation
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn perm(ix: usize) -> usize {
+    PERMUTATION[ix & 255] as usize
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+// improved gradient noise in roughly [-1, 1], zero at every integer lattice point
+pub fn perlin(point: &Point) -> f64 {
+    let Point { position: p } = *point;
+    let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (x, y, z) = (p.x - fx, p.y - fy, p.z - fz);
+    let (xi, yi, zi) = (
+        fx.rem_euclid(256.0) as usize,
+        fy.rem_euclid(256.0) as usize,
+        fz.rem_euclid(256.0) as usize,
+    );
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = perm(xi) + yi;
+    let aa = perm(a) + zi;
+    let ab = perm(a + 1) + zi;
+    let b = perm(xi + 1) + yi;
+    let ba = perm(b) + zi;
+    let bb = perm(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(perm(aa), x, y, z), grad(perm(ba), x - 1.0, y, z)),
+            lerp(
+                u,
+                grad(perm(ab), x, y - 1.0, z),
+                grad(perm(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(perm(aa + 1), x, y, z - 1.0),
+                grad(perm(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(perm(ab + 1), x, y - 1.0, z - 1.0),
+                grad(perm(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+// fractal brownian motion: octaves of noise at doubling frequency and halving amplitude
+pub fn fbm(point: &Point, octaves: usize) -> f64 {
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+    let mut amplitude = 1.0;
+    let mut max = 0.0;
+    for _ in 0..octaves.max(1) {
+        total += perlin(&Point::from(point.position * frequency)) * amplitude;
+        max += amplitude;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+    total / max
+}
+
+// like fbm but sums absolute values, giving the creased look used for marble and fire
+pub fn turbulence(point: &Point, octaves: usize) -> f64 {
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+    let mut amplitude = 1.0;
+    let mut max = 0.0;
+    for _ in 0..octaves.max(1) {
+        total += perlin(&Point::from(point.position * frequency)).abs() * amplitude;
+        max += amplitude;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+    total / max
+}
+
+#[cfg(test)]
+mod noise_tests {
+    use super::*;
+
+    #[test]
+    fn zero_on_lattice_points() {
+        assert_eq!(perlin(&Point::new(0.0, 0.0, 0.0)), 0.0);
+        assert_eq!(perlin(&Point::new(3.0, -7.0, 12.0)), 0.0);
+    }
+
+    #[test]
+    fn deterministic_and_bounded() {
+        for ix in 0..200 {
+            let f = ix as f64 * 0.37;
+            let p = Point::new(f, -f * 0.5, f * 1.3 - 20.0);
+            let n = perlin(&p);
+            assert_eq!(n, perlin(&p));
+            assert!((-1.0..=1.0).contains(&n));
+            assert!((-1.0..=1.0).contains(&fbm(&p, 4)));
+            assert!((0.0..=1.0).contains(&turbulence(&p, 4)));
+        }
+    }
+
+    #[test]
+    fn varies_between_lattice_points() {
+        let a = perlin(&Point::new(0.5, 0.5, 0.5));
+        let b = perlin(&Point::new(1.5, 0.25, 0.75));
+        assert_ne!(a, b);
+    }
+}
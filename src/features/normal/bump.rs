@@ -0,0 +1,72 @@
+use super::{tilt, DELTA};
+use crate::features::{error::Result, matrice::Matrice, noise::fbm, point::Point, vector::Vector};
+
+// procedural bumps from fractal noise, e.g. water ripples or rough plaster
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bump {
+    amount: f64,
+    octaves: usize,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl Bump {
+    pub fn new(amount: f64, octaves: usize, transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            amount,
+            octaves,
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    pub fn perturb(&self, point: &Point, normal: Vector) -> Vector {
+        let height = |offset: Vector| fbm(&(*point + offset), self.octaves);
+        let axis = |x: f64, y: f64, z: f64| {
+            let step = Vector::new(x, y, z) * DELTA;
+            (height(step) - height(-step)) / (2.0 * DELTA)
+        };
+        let slope = Vector::new(
+            axis(1.0, 0.0, 0.0),
+            axis(0.0, 1.0, 0.0),
+            axis(0.0, 0.0, 1.0),
+        );
+        tilt(normal, slope, self.amount)
+    }
+}
+
+#[cfg(test)]
+mod bump_tests {
+    use super::*;
+
+    #[test]
+    fn zero_amount_keeps_the_normal() {
+        let bump = Bump::new(0.0, 3, Matrice::identity_matrix(4)).unwrap();
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(bump.perturb(&Point::new(0.3, 0.0, 0.7), normal), normal);
+    }
+
+    #[test]
+    fn tilts_but_stays_unit_and_facing_out() {
+        let bump = Bump::new(0.5, 3, Matrice::identity_matrix(4)).unwrap();
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let mut tilted = 0;
+        for ix in 0..50 {
+            let point = Point::new(ix as f64 * 0.13, 0.0, ix as f64 * -0.07);
+            let n = bump.perturb(&point, normal);
+            assert!((n.magnitude() - 1.0).abs() < 1e-9);
+            assert!(n.dot_product(&normal) > 0.0);
+            if n != normal {
+                tilted += 1;
+            }
+        }
+        assert!(tilted > 40);
+    }
+}
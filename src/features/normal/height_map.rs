@@ -0,0 +1,90 @@
+use super::{tangent_plane, tilt, uv_derivatives};
+use crate::features::{
+    error::Result,
+    matrice::Matrice,
+    pattern::uv::{UvImage, UvMapping},
+    point::Point,
+    vector::Vector,
+};
+
+// embosses a surface with a grayscale image, brighter texels being higher
+#[derive(Debug, PartialEq, Clone)]
+pub struct HeightMap {
+    mapping: UvMapping,
+    image: UvImage,
+    amount: f64,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl HeightMap {
+    pub fn new(
+        mapping: UvMapping,
+        image: UvImage,
+        amount: f64,
+        transform: Matrice,
+    ) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            mapping,
+            image,
+            amount,
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    fn height(&self, u: f64, v: f64) -> f64 {
+        let rgb = self.image.uv_at(u, v).rgb;
+        (rgb.x + rgb.y + rgb.z) / 3.0
+    }
+
+    pub fn perturb(&self, point: &Point, normal: Vector) -> Vector {
+        let (u, v) = self.mapping.map(point);
+        // one texel either side, so the slope sees the image at its own resolution
+        let (width, height) = self.image.get_size();
+        let (su, sv) = (1.0 / width.max(1) as f64, 1.0 / height.max(1) as f64);
+        let dh_du = (self.height(u + su, v) - self.height(u - su, v)) / (2.0 * su);
+        let dh_dv = (self.height(u, v + sv) - self.height(u, v - sv)) / (2.0 * sv);
+        // chain rule through the mapping gives the slope along the surface
+        let (e1, e2) = tangent_plane(&normal);
+        let [du1, du2, dv1, dv2] = uv_derivatives(&self.mapping, point, &e1, &e2);
+        let slope = e1 * (dh_du * du1 + dh_dv * dv1) + e2 * (dh_du * du2 + dh_dv * dv2);
+        tilt(normal, slope, self.amount)
+    }
+}
+
+#[cfg(test)]
+mod height_map_tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::features::{canvas::Canvas, color::Color};
+
+    #[test]
+    fn normal_leans_away_from_rising_ground() {
+        // a ramp getting brighter towards +u, which the planar mapping lays along +x
+        let mut canvas = Canvas::new(4, 1);
+        for x in 0..4 {
+            let c = x as f64 / 3.0;
+            canvas.canvas[0][x] = Color::new(c, c, c);
+        }
+        let map = HeightMap::new(
+            UvMapping::Planar,
+            UvImage::new(Arc::new(canvas)),
+            0.1,
+            Matrice::identity_matrix(4),
+        )
+        .unwrap();
+        let n = map.perturb(&Point::new(0.5, 0.0, 0.5), Vector::new(0.0, 1.0, 0.0));
+        assert!(n.position.x < -0.01, "{:?}", n);
+        assert!(n.position.z.abs() < 1e-9, "{:?}", n);
+        assert!((n.magnitude() - 1.0).abs() < 1e-9);
+    }
+}
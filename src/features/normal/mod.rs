@@ -0,0 +1,178 @@
+use self::{bump::Bump, height_map::HeightMap, normal_map::NormalMap};
+
+use super::{matrice::Matrice, pattern::uv::UvMapping, point::Point, shape::Shape, vector::Vector};
+pub mod bump;
+pub mod height_map;
+pub mod normal_map;
+
+// step used for the finite differences that estimate surface derivatives
+const DELTA: f64 = 0.0001;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum NormalModifier {
+    Bump(Bump),
+    HeightMap(HeightMap),
+    NormalMap(NormalMap),
+}
+
+impl NormalModifier {
+    // perturbs a geometric world space normal; the modifier works in its own
+    // space, placed on the object by its transform the same way patterns are
+    pub fn apply(&self, shape: &dyn Shape, world_point: &Point, world_normal: Vector) -> Vector {
+        let point = self.get_inverse().clone() * (shape.get_inverse().clone() * *world_point);
+        let normal =
+            self.get_transform().transpose() * (shape.get_transform().transpose() * world_normal);
+        let perturbed = self.perturb(&point, normal.normalize());
+        let object_normal = self.get_inverse().transpose() * perturbed;
+        (shape.get_inverse().transpose() * object_normal).normalize()
+    }
+    pub fn perturb(&self, point: &Point, normal: Vector) -> Vector {
+        match self {
+            NormalModifier::Bump(b) => b.perturb(point, normal),
+            NormalModifier::HeightMap(h) => h.perturb(point, normal),
+            NormalModifier::NormalMap(n) => n.perturb(point, normal),
+        }
+    }
+    pub fn get_transform(&self) -> Matrice {
+        match self {
+            NormalModifier::Bump(b) => b.get_transform(),
+            NormalModifier::HeightMap(h) => h.get_transform(),
+            NormalModifier::NormalMap(n) => n.get_transform(),
+        }
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        match self {
+            NormalModifier::Bump(b) => b.get_inverse(),
+            NormalModifier::HeightMap(h) => h.get_inverse(),
+            NormalModifier::NormalMap(n) => n.get_inverse(),
+        }
+    }
+}
+
+// tilts the normal against the slope of a height field along the surface
+fn tilt(normal: Vector, slope: Vector, amount: f64) -> Vector {
+    let along_surface = slope - normal * slope.dot_product(&normal);
+    (normal - along_surface * amount).normalize()
+}
+
+// any two unit vectors spanning the plane perpendicular to the normal
+fn tangent_plane(normal: &Vector) -> (Vector, Vector) {
+    let helper = if normal.position.x.abs() > 0.9 {
+        Vector::new(0.0, 1.0, 0.0)
+    } else {
+        Vector::new(1.0, 0.0, 0.0)
+    };
+    let e1 = normal.cross_product(&helper).normalize();
+    let e2 = normal.cross_product(&e1);
+    (e1, e2)
+}
+
+// how fast u and v change when moving along e1 and e2, as [du/de1, du/de2, dv/de1, dv/de2];
+// estimated numerically so every uv mapping works, unwrapping the seam where u or v jump
+fn uv_derivatives(mapping: &UvMapping, point: &Point, e1: &Vector, e2: &Vector) -> [f64; 4] {
+    let wrapped = |d: f64| d - d.round();
+    let derivative = |e: &Vector| {
+        let (u1, v1) = mapping.map(&(*point + *e * DELTA));
+        let (u0, v0) = mapping.map(&(*point + *e * -DELTA));
+        (
+            wrapped(u1 - u0) / (2.0 * DELTA),
+            wrapped(v1 - v0) / (2.0 * DELTA),
+        )
+    };
+    let (du1, dv1) = derivative(e1);
+    let (du2, dv2) = derivative(e2);
+    [du1, du2, dv1, dv2]
+}
+
+#[cfg(test)]
+mod normal_modifier_tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::features::{
+        canvas::Canvas,
+        color::Color,
+        computation::Computation,
+        intersection::Intersection,
+        material::Material,
+        pattern::uv::UvImage,
+        ray::Ray,
+        shape::{plane::Plane, sphere::Sphere},
+        transformations::{scaling, translation},
+    };
+
+    fn assert_vector(actual: Vector, expected: Vector) {
+        let diff = actual - expected;
+        assert!(diff.magnitude() < 1e-4, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn tangent_plane_is_orthonormal() {
+        for normal in [
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 0.6, -0.8),
+        ] {
+            let (e1, e2) = tangent_plane(&normal);
+            assert!(e1.dot_product(&normal).abs() < 1e-12);
+            assert!(e2.dot_product(&normal).abs() < 1e-12);
+            assert!(e1.dot_product(&e2).abs() < 1e-12);
+            assert!((e1.magnitude() - 1.0).abs() < 1e-12);
+            assert!((e2.magnitude() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn uv_derivatives_unwrap_the_seam() {
+        let (e1, e2) = (Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let d = uv_derivatives(&UvMapping::Planar, &Point::new(2.0, 0.0, 0.5), &e1, &e2);
+        for (actual, expected) in d.iter().zip([1.0, 0.0, 0.0, 1.0]) {
+            assert!((actual - expected).abs() < 1e-6, "{:?}", d);
+        }
+    }
+
+    #[test]
+    fn modifier_follows_the_shape_transform() {
+        // a flat height map leaves the normal alone, whatever the transforms
+        let flat = Arc::new(Canvas::new(2, 2));
+        let modifier = NormalModifier::HeightMap(
+            HeightMap::new(
+                UvMapping::Spherical,
+                UvImage::new(flat),
+                1.0,
+                scaling(2.0, 1.0, 1.0),
+            )
+            .unwrap(),
+        );
+        let mut sphere = Sphere::default();
+        sphere.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        let point = Point::new(0.0, 1.0, -1.0);
+        let normal = sphere.normal_at(point);
+        assert_vector(modifier.apply(&sphere, &point, normal), normal);
+    }
+
+    #[test]
+    fn computation_uses_the_material_modifier() {
+        let mut plane = Plane::default();
+        let mut canvas = Canvas::new(1, 1);
+        canvas.canvas[0][0] = Color::new(1.0, 0.5, 0.5);
+        let modifier = NormalModifier::NormalMap(
+            NormalMap::new(
+                UvMapping::Planar,
+                UvImage::new(Arc::new(canvas)),
+                Matrice::identity_matrix(4),
+            )
+            .unwrap(),
+        );
+        plane
+            .set_material(Material {
+                normal: Some(modifier),
+                ..Default::default()
+            })
+            .unwrap();
+        let r = Ray::new(Point::new(0.3, 1.0, 0.3), Vector::new(0.0, -1.0, 0.0));
+        let i = Intersection::new(&plane.get_shape_id(), 1.0);
+        let comps = Computation::new(&r, &i, &plane);
+        assert_vector(comps.normalv, Vector::new(1.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,102 @@
+use super::{tangent_plane, uv_derivatives};
+use crate::features::{
+    error::Result,
+    matrice::Matrice,
+    pattern::uv::{UvImage, UvMapping},
+    point::Point,
+    vector::Vector,
+};
+
+// tangent space normal map: red runs along u, green along v and blue along the normal
+#[derive(Debug, PartialEq, Clone)]
+pub struct NormalMap {
+    mapping: UvMapping,
+    image: UvImage,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl NormalMap {
+    pub fn new(mapping: UvMapping, image: UvImage, transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            mapping,
+            image,
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    pub fn perturb(&self, point: &Point, normal: Vector) -> Vector {
+        let (e1, e2) = tangent_plane(&normal);
+        let [du1, du2, dv1, dv2] = uv_derivatives(&self.mapping, point, &e1, &e2);
+        let det = du1 * dv2 - du2 * dv1;
+        // the mapping is degenerate here (e.g. a pole), so there is no tangent frame
+        if det.abs() < 1e-12 {
+            return normal;
+        }
+        // invert the uv jacobian to get the surface directions of increasing u and v
+        let along_u = (e1 * dv2 - e2 * dv1) * (1.0 / det);
+        let along_v = (e2 * du1 - e1 * du2) * (1.0 / det);
+        let tangent = (along_u - normal * along_u.dot_product(&normal)).normalize();
+        let mut bitangent = normal.cross_product(&tangent);
+        if bitangent.dot_product(&along_v) < 0.0 {
+            bitangent = -bitangent;
+        }
+        let (u, v) = self.mapping.map(point);
+        let rgb = self.image.uv_at(u, v).rgb;
+        (tangent * (2.0 * rgb.x - 1.0)
+            + bitangent * (2.0 * rgb.y - 1.0)
+            + normal * (2.0 * rgb.z - 1.0))
+            .normalize()
+    }
+}
+
+#[cfg(test)]
+mod normal_map_tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::features::{canvas::Canvas, color::Color};
+
+    fn single_texel(color: Color) -> UvImage {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.canvas[0][0] = color;
+        UvImage::new(Arc::new(canvas))
+    }
+
+    #[test]
+    fn flat_texel_keeps_the_normal() {
+        let map = NormalMap::new(
+            UvMapping::Spherical,
+            single_texel(Color::new(0.5, 0.5, 1.0)),
+            Matrice::identity_matrix(4),
+        )
+        .unwrap();
+        let normal = Vector::new(0.0, 0.0, -1.0);
+        let n = map.perturb(&Point::new(0.0, 0.0, -1.0), normal);
+        assert!((n - normal).magnitude() < 1e-9, "{:?}", n);
+    }
+
+    #[test]
+    fn green_leans_towards_increasing_v() {
+        let map = NormalMap::new(
+            UvMapping::Planar,
+            single_texel(Color::new(0.5, 1.0, 0.5)),
+            Matrice::identity_matrix(4),
+        )
+        .unwrap();
+        let n = map.perturb(&Point::new(0.5, 0.0, 0.5), Vector::new(0.0, 1.0, 0.0));
+        assert!(
+            (n - Vector::new(0.0, 0.0, 1.0)).magnitude() < 1e-6,
+            "{:?}",
+            n
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use rand::Rng;
+
+use super::{
+    color::Color, computation::Computation, consts::WHITE, error::Result,
+    integrator::cosine_hemisphere, intersection::hit, ray::Ray, world::World,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AmbientOcclusion {
+    pub samples: usize,
+    // anything further away than this doesn't occlude
+    pub distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, distance: f64) -> Self {
+        AmbientOcclusion { samples, distance }
+    }
+
+    // the share of cosine weighted hemisphere rays that travel the full distance
+    // unblocked: 1 in the open, falling towards 0 in creases and contact points
+    pub fn estimate<R: Rng>(&self, world: &World, comps: &Computation, rng: &mut R) -> f64 {
+        let samples = self.samples.max(1);
+        let open = (0..samples)
+            .filter(|_| {
+                let direction = cosine_hemisphere(&comps.normalv, rng);
+                let target = comps.over_point + direction * self.distance;
+                !world.is_blocked(&comps.over_point, &target, 0.0, comps.time)
+            })
+            .count();
+        open as f64 / samples as f64
+    }
+
+    // the grayscale render mode, where nothing at all occludes the sky
+    pub fn radiance<R: Rng>(&self, world: &World, ray: &Ray, rng: &mut R) -> Result<Color> {
+        let hit = match world.intersect(ray).and_then(hit) {
+            Some(hit) => hit,
+            None => return Ok(WHITE),
+        };
+        let comps = Computation::new(ray, &hit, &world.get_shape_at(&hit.shape_id, ray.time)?);
+        Ok(WHITE * self.estimate(world, &comps, rng))
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        AmbientOcclusion::new(16, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod ambient_occlusion_tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::features::{
+        consts::BLACK,
+        light::Light,
+        point::Point,
+        shape::{plane::Plane, sphere::Sphere, Shape},
+        transformations::translation,
+        vector::Vector,
+    };
+
+    fn floor_world() -> World {
+        let mut world = World::new(Light::new(Point::new(0.0, 10.0, 0.0), WHITE));
+        world.add_shape(Box::<Plane>::default());
+        world
+    }
+
+    fn floor_occlusion(world: &World, x: f64, ao: &AmbientOcclusion) -> f64 {
+        let ray = Ray::new(Point::new(x, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let hit = hit(world.intersect(&ray).unwrap()).unwrap();
+        let comps = Computation::new(&ray, &hit, world.get_shape(&hit.shape_id).unwrap());
+        ao.estimate(world, &comps, &mut StdRng::seed_from_u64(3))
+    }
+
+    #[test]
+    fn open_floor_is_unoccluded() {
+        let world = floor_world();
+        assert_eq!(
+            floor_occlusion(&world, 0.0, &AmbientOcclusion::default()),
+            1.0
+        );
+    }
+
+    #[test]
+    fn contact_points_are_darker_within_the_distance() {
+        let mut world = floor_world();
+        let mut sphere = Sphere::default();
+        sphere.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        world.add_shape(Box::new(sphere));
+        let ao = AmbientOcclusion::new(256, 2.0);
+        let near = floor_occlusion(&world, 1.1, &ao);
+        let further = floor_occlusion(&world, 2.5, &ao);
+        assert!(near < further, "{} {}", near, further);
+        assert!(further < 1.0);
+        // with a short reach the sphere is too far away to count
+        let short = AmbientOcclusion::new(256, 0.01);
+        assert_eq!(floor_occlusion(&world, 1.5, &short), 1.0);
+    }
+
+    #[test]
+    fn render_mode_is_grayscale() {
+        let world = floor_world();
+        let ao = AmbientOcclusion::default();
+        let mut rng = StdRng::seed_from_u64(1);
+        let down = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let up = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(ao.radiance(&world, &down, &mut rng).unwrap(), WHITE);
+        assert_eq!(ao.radiance(&world, &up, &mut rng).unwrap(), WHITE);
+        assert_ne!(ao.radiance(&world, &down, &mut rng).unwrap(), BLACK);
+    }
+}
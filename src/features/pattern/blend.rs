@@ -0,0 +1,61 @@
+use super::Pattern;
+use crate::features::{color::Color, error::Result, matrice::Matrice, point::Point};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Blend {
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl Blend {
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>, transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    pub fn at(&self, point: &Point) -> Color {
+        (self.a.at_transformed(point) + self.b.at_transformed(point)) * 0.5
+    }
+}
+
+#[cfg(test)]
+mod blend_tests {
+    use crate::features::{
+        consts::{BLACK, WHITE},
+        pattern::stripe::Stripe,
+        transformations::rotation_y,
+    };
+
+    use super::*;
+
+    #[test]
+    fn averages_two_colors() {
+        let p = Blend::new(WHITE, BLACK, Matrice::identity_matrix(4)).unwrap();
+        assert_eq!(p.at(&Point::new(3.0, 1.0, 2.0)), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn crossed_stripes_use_their_own_transforms() {
+        let along_x = Pattern::Stripe(Stripe::default());
+        let along_z = Pattern::Stripe(
+            Stripe::new(WHITE, BLACK, rotation_y(std::f64::consts::PI / 2.0)).unwrap(),
+        );
+        let p = Blend::new(along_x, along_z, Matrice::identity_matrix(4)).unwrap();
+        assert_eq!(p.at(&Point::new(0.5, 0.0, -0.5)), WHITE);
+        assert_eq!(p.at(&Point::new(0.5, 0.0, 0.5)), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(p.at(&Point::new(1.5, 0.0, 0.5)), BLACK);
+    }
+}
@@ -1,37 +1,124 @@
+use super::{is_even, Pattern};
 use crate::features::{
     color::Color,
-    consts::{BLACK, WHITE},
+    consts::{BLACK, EPSILON, WHITE},
+    error::Result,
     matrice::Matrice,
     point::Point,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Checker {
-    a: Color,
-    b: Color,
+    a: Box<Pattern>,
+    b: Box<Pattern>,
     transform: Matrice,
+    inverse: Matrice,
+    offset: f64,
 }
 
 impl Checker {
-    pub fn new(a: Color, b: Color, transform: Matrice) -> Self {
-        Self { a, b, transform }
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>, transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transform,
+            inverse,
+            offset: 0.0,
+        })
+    }
+    // nudges every coordinate by a small epsilon, so a plane lying exactly on a
+    // cell boundary doesn't flicker between cells from floating point error
+    pub fn acne_resistant(
+        a: impl Into<Pattern>,
+        b: impl Into<Pattern>,
+        transform: Matrice,
+    ) -> Result<Self> {
+        let mut checker = Self::new(a, b, transform)?;
+        checker.set_offset(EPSILON);
+        Ok(checker)
+    }
+    pub fn get_offset(&self) -> f64 {
+        self.offset
+    }
+    pub fn set_offset(&mut self, offset: f64) {
+        self.offset = offset;
     }
     pub fn get_transform(&self) -> Matrice {
         self.transform.clone()
     }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
 
     pub fn at(&self, point: &Point) -> Color {
-        if (point.position.x.abs() + point.position.y.abs() + point.position.z.abs()) as i32 % 2
-            == 0
-        {
-            return self.a;
+        let Point { position: p } = *point;
+        let cells =
+            (p.x + self.offset).floor() + (p.y + self.offset).floor() + (p.z + self.offset).floor();
+        if is_even(cells) {
+            return self.a.at_transformed(point);
         }
-        self.b
+        self.b.at_transformed(point)
     }
 }
 
 impl Default for Checker {
     fn default() -> Self {
-        Self::new(WHITE, BLACK, Matrice::identity_matrix(4))
+        Self {
+            a: Box::new(WHITE.into()),
+            b: Box::new(BLACK.into()),
+            transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
+            offset: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod checker_tests {
+    use super::*;
+
+    #[test]
+    fn repeats_in_each_axis() {
+        let p = Checker::default();
+        for (x, y, z, expected) in [
+            (0.0, 0.0, 0.0, WHITE),
+            (0.99, 0.0, 0.0, WHITE),
+            (1.01, 0.0, 0.0, BLACK),
+            (0.0, 0.99, 0.0, WHITE),
+            (0.0, 1.01, 0.0, BLACK),
+            (0.0, 0.0, 0.99, WHITE),
+            (0.0, 0.0, 1.01, BLACK),
+        ] {
+            assert_eq!(p.at(&Point::new(x, y, z)), expected);
+        }
+    }
+
+    #[test]
+    fn no_doubled_cell_around_the_origin() {
+        let p = Checker::default();
+        for (x, y, z, expected) in [
+            (-0.5, 0.0, 0.0, BLACK),
+            (-1.5, 0.0, 0.0, WHITE),
+            (0.0, -0.5, 0.0, BLACK),
+            (0.0, 0.0, -0.5, BLACK),
+            (-0.5, -0.5, 0.0, WHITE),
+            (-0.5, -0.5, -0.5, BLACK),
+            (-2.5, 0.5, 3.5, WHITE),
+        ] {
+            assert_eq!(p.at(&Point::new(x, y, z)), expected);
+        }
+    }
+
+    #[test]
+    fn acne_resistant_on_cell_boundaries() {
+        let plain = Checker::default();
+        let robust = Checker::acne_resistant(WHITE, BLACK, Matrice::identity_matrix(4)).unwrap();
+        assert_eq!(robust.get_offset(), EPSILON);
+        // a hit on a y = 0 plane that landed just below the surface
+        let p = Point::new(0.5, -1e-12, 0.5);
+        assert_eq!(plain.at(&p), BLACK);
+        assert_eq!(robust.at(&p), WHITE);
+        assert_eq!(robust.at(&Point::new(-0.5, 1e-12, 0.5)), BLACK);
     }
 }
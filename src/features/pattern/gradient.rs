@@ -1,38 +1,56 @@
+use super::Pattern;
 use crate::features::{
     color::Color,
     consts::{BLACK, WHITE},
+    error::Result,
     matrice::Matrice,
     point::Point,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Gradient {
-    from: Color,
-    to: Color,
+    from: Box<Pattern>,
+    to: Box<Pattern>,
     transform: Matrice,
+    inverse: Matrice,
 }
 
 impl Gradient {
-    pub fn new(from: Color, to: Color, transform: Matrice) -> Self {
-        Self {
-            from,
-            to,
+    pub fn new(
+        from: impl Into<Pattern>,
+        to: impl Into<Pattern>,
+        transform: Matrice,
+    ) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            from: Box::new(from.into()),
+            to: Box::new(to.into()),
             transform,
-        }
+            inverse,
+        })
     }
     pub fn get_transform(&self) -> Matrice {
         self.transform.clone()
     }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
 
     pub fn at(&self, point: &Point) -> Color {
-        let distance = self.to - self.from;
+        let from = self.from.at_transformed(point);
+        let distance = self.to.at_transformed(point) - from;
         let fraction = point.position.x - point.position.x.floor();
-        self.from + distance * fraction
+        from + distance * fraction
     }
 }
 
 impl Default for Gradient {
     fn default() -> Self {
-        Self::new(WHITE, BLACK, Matrice::identity_matrix(4))
+        Self {
+            from: Box::new(WHITE.into()),
+            to: Box::new(BLACK.into()),
+            transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
+        }
     }
 }
@@ -1,49 +1,148 @@
-use self::{checker::Checker, gradient::Gradient, ring::Ring, stripe::Stripe};
-
-use super::{
-    color::Color,
-    matrice::Matrice,
-    point::{self, Point},
-    shape::Shape,
+use self::{
+    blend::Blend,
+    checker::Checker,
+    gradient::Gradient,
+    perturbed::Perturbed,
+    radial_gradient::RadialGradient,
+    ring::Ring,
+    solid::Solid,
+    stripe::Stripe,
+    texture_map::{CubeMap, TextureMap},
 };
+
+use super::{color::Color, matrice::Matrice, point::Point, shape::Shape};
+pub mod blend;
 pub mod checker;
 pub mod gradient;
+pub mod perturbed;
+pub mod radial_gradient;
 pub mod ring;
+pub mod solid;
 pub mod stripe;
+pub mod texture_map;
+pub mod uv;
 #[derive(Debug, PartialEq, Clone)]
 pub enum Pattern {
+    Solid(Solid),
     Stripe(Stripe),
     Checker(Checker),
     Ring(Ring),
     Gradient(Gradient),
+    RadialGradient(RadialGradient),
+    Blend(Blend),
+    Perturbed(Perturbed),
+    TextureMap(TextureMap),
+    CubeMap(CubeMap),
+}
+
+// floor-based parity, so cells keep their width on both sides of the origin
+fn is_even(value: f64) -> bool {
+    value.floor().rem_euclid(2.0) == 0.0
 }
 
 impl Pattern {
-    pub fn at_obj(&self, shape: &dyn Shape, point: &Point) -> Option<Color> {
-        let object_point = shape.get_transform().inverse()? * *point;
-        let pattern_point = self.get_transform().inverse()? * object_point;
-        let res_color = match self {
-            Pattern::Stripe(s) => s.at(&pattern_point),
-            Pattern::Checker(c) => c.at(&pattern_point),
-            Pattern::Ring(r) => r.at(&pattern_point),
-            Pattern::Gradient(g) => g.at(&pattern_point),
-        };
-        Some(res_color)
+    pub fn at_obj(&self, shape: &dyn Shape, point: &Point) -> Color {
+        let object_point = shape.get_inverse().clone() * *point;
+        self.at_transformed(&object_point)
+    }
+    // evaluates the pattern at a point given in its parent's space, so nested
+    // patterns are positioned by their own transform
+    pub fn at_transformed(&self, point: &Point) -> Color {
+        if let Pattern::Solid(s) = self {
+            return s.at();
+        }
+        let pattern_point = self.get_inverse().clone() * *point;
+        self.at(&pattern_point)
     }
     pub fn get_transform(&self) -> Matrice {
         match self {
+            Pattern::Solid(s) => s.get_transform(),
             Pattern::Stripe(s) => s.get_transform(),
             Pattern::Checker(c) => c.get_transform(),
             Pattern::Ring(r) => r.get_transform(),
             Pattern::Gradient(g) => g.get_transform(),
+            Pattern::RadialGradient(g) => g.get_transform(),
+            Pattern::Blend(b) => b.get_transform(),
+            Pattern::Perturbed(p) => p.get_transform(),
+            Pattern::TextureMap(t) => t.get_transform(),
+            Pattern::CubeMap(c) => c.get_transform(),
+        }
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        match self {
+            Pattern::Solid(s) => s.get_inverse(),
+            Pattern::Stripe(s) => s.get_inverse(),
+            Pattern::Checker(c) => c.get_inverse(),
+            Pattern::Ring(r) => r.get_inverse(),
+            Pattern::Gradient(g) => g.get_inverse(),
+            Pattern::RadialGradient(g) => g.get_inverse(),
+            Pattern::Blend(b) => b.get_inverse(),
+            Pattern::Perturbed(p) => p.get_inverse(),
+            Pattern::TextureMap(t) => t.get_inverse(),
+            Pattern::CubeMap(c) => c.get_inverse(),
         }
     }
     pub fn at(&self, point: &Point) -> Color {
         match self {
+            Pattern::Solid(s) => s.at(),
             Pattern::Stripe(s) => s.at(point),
             Pattern::Checker(c) => c.at(point),
             Pattern::Ring(r) => r.at(point),
             Pattern::Gradient(g) => g.at(point),
+            Pattern::RadialGradient(g) => g.at(point),
+            Pattern::Blend(b) => b.at(point),
+            Pattern::Perturbed(p) => p.at(point),
+            Pattern::TextureMap(t) => t.at(point),
+            Pattern::CubeMap(c) => c.at(point),
         }
     }
 }
+
+impl From<Color> for Pattern {
+    fn from(value: Color) -> Self {
+        Pattern::Solid(Solid::new(value))
+    }
+}
+
+#[cfg(test)]
+mod pattern_tests {
+    use crate::features::{
+        consts::{BLACK, WHITE},
+        shape::sphere::Sphere,
+        transformations::{scaling, translation},
+    };
+
+    use super::*;
+
+    #[test]
+    fn checker_of_stripes() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let stripes = Pattern::Stripe(Stripe::new(red, BLACK, scaling(0.25, 1.0, 1.0)).unwrap());
+        let p =
+            Pattern::Checker(Checker::new(stripes, WHITE, Matrice::identity_matrix(4)).unwrap());
+        assert_eq!(p.at(&Point::new(0.1, 0.0, 0.0)), red);
+        assert_eq!(p.at(&Point::new(0.3, 0.0, 0.0)), BLACK);
+        assert_eq!(p.at(&Point::new(1.1, 0.0, 0.0)), WHITE);
+    }
+
+    #[test]
+    fn nested_pattern_transform_is_relative_to_parent() {
+        let inner = Pattern::Stripe(Stripe::new(WHITE, BLACK, translation(0.5, 0.0, 0.0)).unwrap());
+        let outer =
+            Pattern::Blend(Blend::new(inner.clone(), inner, scaling(2.0, 2.0, 2.0)).unwrap());
+        let shape = Sphere::default();
+        // object x=1.5 -> outer x=0.75 -> inner x=0.25, inside the first stripe
+        assert_eq!(outer.at_obj(&shape, &Point::new(1.5, 0.0, 0.0)), WHITE);
+        // object x=0.5 -> outer x=0.25 -> inner x=-0.25, inside the previous stripe
+        assert_eq!(outer.at_obj(&shape, &Point::new(0.5, 0.0, 0.0)), BLACK);
+    }
+
+    #[test]
+    fn solid_ignores_point() {
+        let p = Pattern::from(Color::new(0.2, 0.4, 0.6));
+        assert_eq!(
+            p.at(&Point::new(12.0, -3.0, 7.5)),
+            Color::new(0.2, 0.4, 0.6)
+        );
+    }
+}
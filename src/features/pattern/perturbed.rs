@@ -0,0 +1,79 @@
+use super::Pattern;
+use crate::features::{
+    color::Color, error::Result, matrice::Matrice, noise::fbm, point::Point, vector::Vector,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Perturbed {
+    pattern: Box<Pattern>,
+    scale: f64,
+    octaves: usize,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl Perturbed {
+    pub fn new(
+        pattern: impl Into<Pattern>,
+        scale: f64,
+        octaves: usize,
+        transform: Matrice,
+    ) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            pattern: Box::new(pattern.into()),
+            scale,
+            octaves,
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    pub fn at(&self, point: &Point) -> Color {
+        // offset the lookups so each axis gets an uncorrelated jitter
+        let jitter = Vector::new(
+            fbm(point, self.octaves),
+            fbm(&(*point + Vector::new(31.4, 0.0, 0.0)), self.octaves),
+            fbm(&(*point + Vector::new(0.0, 0.0, 27.1)), self.octaves),
+        ) * self.scale;
+        self.pattern.at_transformed(&(*point + jitter))
+    }
+}
+
+#[cfg(test)]
+mod perturbed_tests {
+    use crate::features::{
+        consts::{BLACK, WHITE},
+        pattern::stripe::Stripe,
+    };
+
+    use super::*;
+
+    #[test]
+    fn zero_scale_matches_inner_pattern() {
+        let stripe = Pattern::Stripe(Stripe::default());
+        let p = Perturbed::new(stripe.clone(), 0.0, 3, Matrice::identity_matrix(4)).unwrap();
+        for ix in 0..20 {
+            let point = Point::new(ix as f64 * 0.3, 0.2, -0.7);
+            assert_eq!(p.at(&point), stripe.at(&point));
+        }
+    }
+
+    #[test]
+    fn jitter_moves_stripe_edges() {
+        let stripe = Pattern::Stripe(Stripe::default());
+        let p = Perturbed::new(stripe, 1.0, 3, Matrice::identity_matrix(4)).unwrap();
+        let differs = (0..100).any(|ix| {
+            let point = Point::new(0.95 + ix as f64 * 0.001, ix as f64 * 0.13, 0.4);
+            p.at(&point) != WHITE
+        });
+        assert!(differs);
+        assert!([WHITE, BLACK].contains(&p.at(&Point::new(0.3, 0.3, 0.3))));
+    }
+}
@@ -0,0 +1,73 @@
+use super::Pattern;
+use crate::features::{
+    color::Color,
+    consts::{BLACK, WHITE},
+    error::Result,
+    matrice::Matrice,
+    point::Point,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RadialGradient {
+    from: Box<Pattern>,
+    to: Box<Pattern>,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl RadialGradient {
+    pub fn new(
+        from: impl Into<Pattern>,
+        to: impl Into<Pattern>,
+        transform: Matrice,
+    ) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            from: Box::new(from.into()),
+            to: Box::new(to.into()),
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    pub fn at(&self, point: &Point) -> Color {
+        let from = self.from.at_transformed(point);
+        let distance = self.to.at_transformed(point) - from;
+        let radius =
+            (point.position.x * point.position.x + point.position.z * point.position.z).sqrt();
+        from + distance * (radius - radius.floor())
+    }
+}
+
+impl Default for RadialGradient {
+    fn default() -> Self {
+        Self {
+            from: Box::new(WHITE.into()),
+            to: Box::new(BLACK.into()),
+            transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
+        }
+    }
+}
+
+#[cfg(test)]
+mod radial_gradient_tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_with_distance_from_y_axis() {
+        let p = RadialGradient::default();
+        assert_eq!(p.at(&Point::new(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(
+            p.at(&Point::new(0.0, 5.0, 0.25)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(p.at(&Point::new(0.3, 0.0, 0.4)), Color::new(0.5, 0.5, 0.5));
+    }
+}
@@ -1,38 +1,68 @@
+use super::{is_even, Pattern};
 use crate::features::{
     color::Color,
     consts::{BLACK, WHITE},
+    error::Result,
     matrice::Matrice,
     point::Point,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Ring {
-    a: Color,
-    b: Color,
+    a: Box<Pattern>,
+    b: Box<Pattern>,
     transform: Matrice,
+    inverse: Matrice,
 }
 
 impl Ring {
-    pub fn new(a: Color, b: Color, transform: Matrice) -> Ring {
-        Ring { a, b, transform }
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>, transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transform,
+            inverse,
+        })
     }
     pub fn get_transform(&self) -> Matrice {
         self.transform.clone()
     }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
 
     pub fn at(&self, point: &Point) -> Color {
-        if (point.position.x * point.position.x + point.position.z * point.position.z).sqrt() as i32
-            % 2
-            == 0
-        {
-            return self.a;
+        let Point { position: p } = *point;
+        if is_even((p.x * p.x + p.z * p.z).sqrt()) {
+            return self.a.at_transformed(point);
         }
-        self.b
+        self.b.at_transformed(point)
     }
 }
 
 impl Default for Ring {
     fn default() -> Self {
-        Self::new(WHITE, BLACK, Matrice::identity_matrix(4))
+        Self {
+            a: Box::new(WHITE.into()),
+            b: Box::new(BLACK.into()),
+            transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ring_tests {
+    use super::*;
+
+    #[test]
+    fn extends_in_x_and_z() {
+        let p = Ring::default();
+        assert_eq!(p.at(&Point::new(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(p.at(&Point::new(1.0, 0.0, 0.0)), BLACK);
+        assert_eq!(p.at(&Point::new(0.0, 0.0, -1.0)), BLACK);
+        assert_eq!(p.at(&Point::new(-0.708, 0.0, -0.708)), BLACK);
+        assert_eq!(p.at(&Point::new(-2.5, 0.0, 0.0)), WHITE);
     }
 }
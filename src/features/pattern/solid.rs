@@ -0,0 +1,33 @@
+use crate::features::{color::Color, consts::WHITE, matrice::Matrice};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Solid {
+    color: Color,
+    transform: Matrice,
+}
+
+impl Solid {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            transform: Matrice::identity_matrix(4),
+        }
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    // a solid color looks the same under any transform, so it is its own inverse
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.transform
+    }
+
+    pub fn at(&self) -> Color {
+        self.color
+    }
+}
+
+impl Default for Solid {
+    fn default() -> Self {
+        Self::new(WHITE)
+    }
+}
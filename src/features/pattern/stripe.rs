@@ -1,36 +1,53 @@
+use super::{is_even, Pattern};
 use crate::features::{
     color::Color,
     consts::{BLACK, WHITE},
+    error::Result,
     matrice::Matrice,
     point::Point,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Stripe {
-    a: Color,
-    b: Color,
+    a: Box<Pattern>,
+    b: Box<Pattern>,
     transform: Matrice,
+    inverse: Matrice,
 }
 
 impl Stripe {
-    pub fn new(a: Color, b: Color, transform: Matrice) -> Stripe {
-        Self { a, b, transform }
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>, transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transform,
+            inverse,
+        })
     }
     pub fn get_transform(&self) -> Matrice {
         self.transform.clone()
     }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
 
     pub fn at(&self, point: &Point) -> Color {
-        if point.position.x.floor() as i32 % 2 == 0 {
-            return self.a;
+        if is_even(point.position.x) {
+            return self.a.at_transformed(point);
         }
-        self.b
+        self.b.at_transformed(point)
     }
 }
 
 impl Default for Stripe {
     fn default() -> Self {
-        Self::new(WHITE, BLACK, Matrice::identity_matrix(4))
+        Self {
+            a: Box::new(WHITE.into()),
+            b: Box::new(BLACK.into()),
+            transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
+        }
     }
 }
 
@@ -39,9 +56,9 @@ mod stripe_tests {
     use super::*;
     #[test]
     fn new() {
-        let p = Stripe::new(WHITE, BLACK, Matrice::identity_matrix(4));
-        assert_eq!(p.a, WHITE);
-        assert_eq!(p.b, BLACK);
+        let p = Stripe::new(WHITE, BLACK, Matrice::identity_matrix(4)).unwrap();
+        assert_eq!(*p.a, Pattern::from(WHITE));
+        assert_eq!(*p.b, Pattern::from(BLACK));
     }
 
     #[test]
@@ -66,4 +83,14 @@ mod stripe_tests {
         assert_eq!(p.at(&Point::new(0.9, 0.0, 0.0)), WHITE);
         assert_eq!(p.at(&Point::new(1.0, 0.0, 0.0)), BLACK);
     }
+
+    #[test]
+    fn alternate_across_the_origin() {
+        let p = Stripe::default();
+        assert_eq!(p.at(&Point::new(-0.1, 0.0, 0.0)), BLACK);
+        assert_eq!(p.at(&Point::new(-1.0, 0.0, 0.0)), BLACK);
+        assert_eq!(p.at(&Point::new(-1.1, 0.0, 0.0)), WHITE);
+        assert_eq!(p.at(&Point::new(-2.0, 0.0, 0.0)), WHITE);
+        assert_eq!(p.at(&Point::new(-2.5, 0.0, 0.0)), BLACK);
+    }
 }
@@ -0,0 +1,118 @@
+use super::uv::{CubeFace, UvMapping, UvPattern};
+use crate::features::{color::Color, error::Result, matrice::Matrice, point::Point};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextureMap {
+    mapping: UvMapping,
+    uv_pattern: UvPattern,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl TextureMap {
+    pub fn new(mapping: UvMapping, uv_pattern: UvPattern, transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            mapping,
+            uv_pattern,
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    pub fn at(&self, point: &Point) -> Color {
+        let (u, v) = self.mapping.map(point);
+        self.uv_pattern.uv_at(u, v)
+    }
+}
+
+// faces are ordered left, front, right, back, up, down
+#[derive(Debug, PartialEq, Clone)]
+pub struct CubeMap {
+    faces: Box<[UvPattern; 6]>,
+    transform: Matrice,
+    inverse: Matrice,
+}
+
+impl CubeMap {
+    pub fn new(faces: [UvPattern; 6], transform: Matrice) -> Result<Self> {
+        let inverse = transform.inverse()?;
+        Ok(Self {
+            faces: Box::new(faces),
+            transform,
+            inverse,
+        })
+    }
+    pub fn get_transform(&self) -> Matrice {
+        self.transform.clone()
+    }
+    pub fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    pub fn at(&self, point: &Point) -> Color {
+        let face = CubeFace::from_point(point);
+        let (u, v) = face.map(point);
+        self.faces[face.index()].uv_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod texture_map_tests {
+    use crate::features::{
+        consts::{BLACK, WHITE},
+        pattern::uv::{AlignCheck, UvCheckers},
+    };
+
+    use super::*;
+
+    #[test]
+    fn checkers_on_a_sphere() {
+        let checkers = UvPattern::Checkers(UvCheckers::new(16.0, 8.0, BLACK, WHITE));
+        let p =
+            TextureMap::new(UvMapping::Spherical, checkers, Matrice::identity_matrix(4)).unwrap();
+        let cases = [
+            (Point::new(0.4315, 0.4670, 0.7719), WHITE),
+            (Point::new(-0.9654, 0.2552, -0.0534), BLACK),
+            (Point::new(0.1039, 0.7090, 0.6975), WHITE),
+            (Point::new(-0.4986, -0.7856, -0.3663), BLACK),
+            (Point::new(-0.0317, -0.9395, 0.3411), BLACK),
+            (Point::new(0.4809, -0.7721, 0.4154), BLACK),
+            (Point::new(0.0285, -0.9612, -0.2745), BLACK),
+            (Point::new(-0.5734, -0.2162, -0.7903), WHITE),
+            (Point::new(0.7688, -0.1470, 0.6223), BLACK),
+            (Point::new(-0.7652, 0.2175, 0.6060), BLACK),
+        ];
+        for (point, color) in cases {
+            assert_eq!(p.at(&point), color);
+        }
+    }
+
+    #[test]
+    fn cube_map_picks_the_face() {
+        let face =
+            |main: Color| UvPattern::AlignCheck(AlignCheck::new(main, BLACK, BLACK, BLACK, BLACK));
+        let colors = [
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 0.0),
+            Color::new(0.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 1.0),
+        ];
+        let p = CubeMap::new(colors.map(face), Matrice::identity_matrix(4)).unwrap();
+        assert_eq!(p.at(&Point::new(-1.0, 0.0, 0.0)), colors[0]);
+        assert_eq!(p.at(&Point::new(0.0, 0.0, 1.0)), colors[1]);
+        assert_eq!(p.at(&Point::new(1.0, 0.0, 0.0)), colors[2]);
+        assert_eq!(p.at(&Point::new(0.0, 0.0, -1.0)), colors[3]);
+        assert_eq!(p.at(&Point::new(0.0, 1.0, 0.0)), colors[4]);
+        assert_eq!(p.at(&Point::new(0.0, -1.0, 0.0)), colors[5]);
+        assert_eq!(p.at(&Point::new(-1.0, 0.9, -0.9)), BLACK);
+    }
+}
@@ -0,0 +1,365 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::features::{
+    canvas::Canvas,
+    color::Color,
+    consts::{BLACK, WHITE},
+    point::Point,
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UvMapping {
+    Spherical,
+    Planar,
+    Cylindrical,
+}
+
+impl UvMapping {
+    pub fn map(&self, point: &Point) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => spherical_map(point),
+            UvMapping::Planar => planar_map(point),
+            UvMapping::Cylindrical => cylindrical_map(point),
+        }
+    }
+}
+
+pub fn spherical_map(point: &Point) -> (f64, f64) {
+    let p = point.position;
+    let theta = p.x.atan2(p.z);
+    let radius = p.magnitude();
+    if radius == 0.0 {
+        return (0.5, 0.5);
+    }
+    let phi = (p.y / radius).clamp(-1.0, 1.0).acos();
+    let raw_u = theta / (2.0 * PI);
+    (1.0 - (raw_u + 0.5), 1.0 - phi / PI)
+}
+
+pub fn planar_map(point: &Point) -> (f64, f64) {
+    (
+        point.position.x.rem_euclid(1.0),
+        point.position.z.rem_euclid(1.0),
+    )
+}
+
+pub fn cylindrical_map(point: &Point) -> (f64, f64) {
+    let theta = point.position.x.atan2(point.position.z);
+    let raw_u = theta / (2.0 * PI);
+    (1.0 - (raw_u + 0.5), point.position.y.rem_euclid(1.0))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    pub fn from_point(point: &Point) -> Self {
+        let p = point.position;
+        let coord = p.x.abs().max(p.y.abs()).max(p.z.abs());
+        if coord == p.x {
+            CubeFace::Right
+        } else if coord == -p.x {
+            CubeFace::Left
+        } else if coord == p.y {
+            CubeFace::Up
+        } else if coord == -p.y {
+            CubeFace::Down
+        } else if coord == p.z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+
+    // maps a point on the unit cube to uv coordinates on this face
+    pub fn map(&self, point: &Point) -> (f64, f64) {
+        let p = point.position;
+        let wrap = |a: f64| a.rem_euclid(2.0) / 2.0;
+        match self {
+            CubeFace::Front => (wrap(p.x + 1.0), wrap(p.y + 1.0)),
+            CubeFace::Back => (wrap(1.0 - p.x), wrap(p.y + 1.0)),
+            CubeFace::Left => (wrap(p.z + 1.0), wrap(p.y + 1.0)),
+            CubeFace::Right => (wrap(1.0 - p.z), wrap(p.y + 1.0)),
+            CubeFace::Up => (wrap(p.x + 1.0), wrap(1.0 - p.z)),
+            CubeFace::Down => (wrap(p.x + 1.0), wrap(p.z + 1.0)),
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum UvPattern {
+    Checkers(UvCheckers),
+    AlignCheck(AlignCheck),
+    Image(UvImage),
+}
+
+impl UvPattern {
+    pub fn uv_at(&self, u: f64, v: f64) -> Color {
+        match self {
+            UvPattern::Checkers(c) => c.uv_at(u, v),
+            UvPattern::AlignCheck(a) => a.uv_at(u, v),
+            UvPattern::Image(i) => i.uv_at(u, v),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct UvCheckers {
+    width: f64,
+    height: f64,
+    a: Color,
+    b: Color,
+}
+
+impl UvCheckers {
+    pub fn new(width: f64, height: f64, a: Color, b: Color) -> Self {
+        Self {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+
+    pub fn uv_at(&self, u: f64, v: f64) -> Color {
+        let u2 = (u * self.width).floor() as i64;
+        let v2 = (v * self.height).floor() as i64;
+        if (u2 + v2).rem_euclid(2) == 0 {
+            return self.a;
+        }
+        self.b
+    }
+}
+
+// a face colored by corner, for checking the orientation of a uv mapping
+#[derive(Debug, PartialEq, Clone)]
+pub struct AlignCheck {
+    main: Color,
+    ul: Color,
+    ur: Color,
+    bl: Color,
+    br: Color,
+}
+
+impl AlignCheck {
+    pub fn new(main: Color, ul: Color, ur: Color, bl: Color, br: Color) -> Self {
+        Self {
+            main,
+            ul,
+            ur,
+            bl,
+            br,
+        }
+    }
+
+    pub fn uv_at(&self, u: f64, v: f64) -> Color {
+        if v > 0.8 {
+            if u < 0.2 {
+                return self.ul;
+            }
+            if u > 0.8 {
+                return self.ur;
+            }
+        } else if v < 0.2 {
+            if u < 0.2 {
+                return self.bl;
+            }
+            if u > 0.8 {
+                return self.br;
+            }
+        }
+        self.main
+    }
+}
+
+impl Default for AlignCheck {
+    fn default() -> Self {
+        Self::new(
+            WHITE,
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 1.0, 1.0),
+        )
+    }
+}
+
+// the canvas is shared so cloning a material does not copy the image
+#[derive(Debug, PartialEq, Clone)]
+pub struct UvImage {
+    canvas: Arc<Canvas>,
+}
+
+impl UvImage {
+    pub fn new(canvas: Arc<Canvas>) -> Self {
+        Self { canvas }
+    }
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.canvas.width, self.canvas.height)
+    }
+
+    pub fn uv_at(&self, u: f64, v: f64) -> Color {
+        let (width, height) = (self.canvas.width, self.canvas.height);
+        if width == 0 || height == 0 {
+            return BLACK;
+        }
+        // v runs bottom to top while canvas rows run top to bottom
+        let x = u.clamp(0.0, 1.0) * (width - 1) as f64;
+        let y = (1.0 - v.clamp(0.0, 1.0)) * (height - 1) as f64;
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+        let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+        let pixel = |x: usize, y: usize| self.canvas.canvas[y][x];
+        let top = pixel(x0, y0) * (1.0 - fx) + pixel(x1, y0) * fx;
+        let bottom = pixel(x0, y1) * (1.0 - fx) + pixel(x1, y1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+#[cfg(test)]
+mod uv_mapping_tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+
+    fn assert_uv(actual: (f64, f64), expected: (f64, f64)) {
+        assert!((actual.0 - expected.0).abs() < 1e-5, "{:?}", actual);
+        assert!((actual.1 - expected.1).abs() < 1e-5, "{:?}", actual);
+    }
+
+    #[test]
+    fn spherical() {
+        assert_uv(spherical_map(&Point::new(0.0, 0.0, -1.0)), (0.0, 0.5));
+        assert_uv(spherical_map(&Point::new(1.0, 0.0, 0.0)), (0.25, 0.5));
+        assert_uv(spherical_map(&Point::new(0.0, 0.0, 1.0)), (0.5, 0.5));
+        assert_uv(spherical_map(&Point::new(-1.0, 0.0, 0.0)), (0.75, 0.5));
+        assert_uv(spherical_map(&Point::new(0.0, 1.0, 0.0)), (0.5, 1.0));
+        assert_uv(spherical_map(&Point::new(0.0, -1.0, 0.0)), (0.5, 0.0));
+        assert_uv(
+            spherical_map(&Point::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0)),
+            (0.25, 0.75),
+        );
+    }
+
+    #[test]
+    fn planar() {
+        assert_uv(planar_map(&Point::new(0.25, 0.0, 0.5)), (0.25, 0.5));
+        assert_uv(planar_map(&Point::new(0.25, 0.0, -0.25)), (0.25, 0.75));
+        assert_uv(planar_map(&Point::new(1.25, 0.0, 0.5)), (0.25, 0.5));
+        assert_uv(planar_map(&Point::new(-0.25, 0.5, -1.75)), (0.75, 0.25));
+    }
+
+    #[test]
+    fn cylindrical() {
+        assert_uv(cylindrical_map(&Point::new(0.0, 0.0, -1.0)), (0.0, 0.0));
+        assert_uv(cylindrical_map(&Point::new(0.0, 0.5, -1.0)), (0.0, 0.5));
+        assert_uv(
+            cylindrical_map(&Point::new(FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2)),
+            (0.125, 0.5),
+        );
+        assert_uv(cylindrical_map(&Point::new(1.0, 0.5, 0.0)), (0.25, 0.5));
+        assert_uv(cylindrical_map(&Point::new(-1.0, -0.25, 0.0)), (0.75, 0.75));
+    }
+
+    #[test]
+    fn cube_faces() {
+        assert_eq!(
+            CubeFace::from_point(&Point::new(-1.0, 0.5, -0.25)),
+            CubeFace::Left
+        );
+        assert_eq!(
+            CubeFace::from_point(&Point::new(1.1, -0.75, 0.8)),
+            CubeFace::Right
+        );
+        assert_eq!(
+            CubeFace::from_point(&Point::new(0.1, 0.6, 0.9)),
+            CubeFace::Front
+        );
+        assert_eq!(
+            CubeFace::from_point(&Point::new(-0.7, 0.0, -2.0)),
+            CubeFace::Back
+        );
+        assert_eq!(
+            CubeFace::from_point(&Point::new(0.5, 1.0, 0.9)),
+            CubeFace::Up
+        );
+        assert_eq!(
+            CubeFace::from_point(&Point::new(-0.2, -1.3, 1.1)),
+            CubeFace::Down
+        );
+    }
+
+    #[test]
+    fn cube_face_uv() {
+        assert_uv(
+            CubeFace::Front.map(&Point::new(-0.5, 0.5, 1.0)),
+            (0.25, 0.75),
+        );
+        assert_uv(
+            CubeFace::Back.map(&Point::new(0.5, -0.5, -1.0)),
+            (0.25, 0.25),
+        );
+        assert_uv(
+            CubeFace::Left.map(&Point::new(-1.0, 0.5, -0.5)),
+            (0.25, 0.75),
+        );
+        assert_uv(
+            CubeFace::Right.map(&Point::new(1.0, -0.5, 0.5)),
+            (0.25, 0.25),
+        );
+        assert_uv(CubeFace::Up.map(&Point::new(-0.5, 1.0, -0.5)), (0.25, 0.75));
+        assert_uv(
+            CubeFace::Down.map(&Point::new(0.5, -1.0, 0.5)),
+            (0.75, 0.75),
+        );
+    }
+}
+
+#[cfg(test)]
+mod uv_pattern_tests {
+    use super::*;
+
+    #[test]
+    fn checkers() {
+        let p = UvCheckers::new(2.0, 2.0, BLACK, WHITE);
+        assert_eq!(p.uv_at(0.0, 0.0), BLACK);
+        assert_eq!(p.uv_at(0.5, 0.0), WHITE);
+        assert_eq!(p.uv_at(0.0, 0.5), WHITE);
+        assert_eq!(p.uv_at(0.5, 0.5), BLACK);
+        assert_eq!(p.uv_at(1.0, 1.0), BLACK);
+    }
+
+    #[test]
+    fn align_check_corners() {
+        let p = AlignCheck::default();
+        assert_eq!(p.uv_at(0.5, 0.5), WHITE);
+        assert_eq!(p.uv_at(0.1, 0.9), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(p.uv_at(0.9, 0.9), Color::new(1.0, 1.0, 0.0));
+        assert_eq!(p.uv_at(0.1, 0.1), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(p.uv_at(0.9, 0.1), Color::new(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn image_lookup_is_bilinear() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.canvas[0][0] = WHITE;
+        canvas.canvas[1][1] = Color::new(1.0, 0.0, 0.0);
+        let p = UvImage::new(Arc::new(canvas));
+        // top left and bottom right texels
+        assert_eq!(p.uv_at(0.0, 1.0), WHITE);
+        assert_eq!(p.uv_at(1.0, 0.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(p.uv_at(0.0, 0.0), BLACK);
+        assert_eq!(p.uv_at(0.5, 0.5), Color::new(0.5, 0.25, 0.25));
+    }
+}
@@ -0,0 +1,203 @@
+use std::f64::consts::PI;
+
+use super::{
+    color::Color,
+    consts::{BLACK, WHITE},
+    light::Light,
+    pattern::Pattern,
+    point::Point,
+    shape::Shape,
+    vector::Vector,
+};
+
+// metallic/roughness parameters for the Cook-Torrance BRDF; every parameter is a
+// pattern so it can vary over the surface, scalars reading the mean of the channels
+#[derive(Debug, PartialEq, Clone)]
+pub struct CookTorrance {
+    pub base_color: Pattern,
+    pub metallic: Pattern,
+    pub roughness: Pattern,
+    pub emissive: Pattern,
+}
+
+// reflectance of dielectrics seen head on
+const DIELECTRIC_F0: f64 = 0.04;
+// below this GGX collapses to a spike that point lights can never hit
+const MIN_ROUGHNESS: f64 = 0.03;
+
+impl CookTorrance {
+    pub fn new(
+        base_color: impl Into<Pattern>,
+        metallic: f64,
+        roughness: f64,
+        emissive: impl Into<Pattern>,
+    ) -> Self {
+        CookTorrance {
+            base_color: base_color.into(),
+            metallic: scalar(metallic),
+            roughness: scalar(roughness),
+            emissive: emissive.into(),
+        }
+    }
+
+    // the matte share of the base color, which metals don't have
+    pub fn albedo(&self, object: &dyn Shape, point: &Point) -> Color {
+        let metallic = mean(self.metallic.at_obj(object, point)).clamp(0.0, 1.0);
+        self.base_color.at_obj(object, point) * (1.0 - metallic)
+    }
+
+    pub fn emitted(&self, object: &dyn Shape, point: &Point) -> Color {
+        self.emissive.at_obj(object, point)
+    }
+
+    // light reflected straight from the light towards the eye, ignoring shadows
+    pub fn direct(
+        &self,
+        light: &Light,
+        object: &dyn Shape,
+        point: &Point,
+        eyev: &Vector,
+        normalv: &Vector,
+    ) -> Color {
+        let base = self.base_color.at_obj(object, point);
+        let metallic = mean(self.metallic.at_obj(object, point)).clamp(0.0, 1.0);
+        let roughness = mean(self.roughness.at_obj(object, point)).clamp(MIN_ROUGHNESS, 1.0);
+
+        let lightv = (light.position - *point).normalize();
+        let n_dot_l = normalv.dot_product(&lightv);
+        if n_dot_l <= 0.0 {
+            return BLACK;
+        }
+        let n_dot_v = normalv.dot_product(eyev).max(1e-4);
+        let halfv = (lightv + *eyev).normalize();
+        let n_dot_h = normalv.dot_product(&halfv).max(0.0);
+        let v_dot_h = eyev.dot_product(&halfv).max(0.0);
+
+        let f0 = WHITE * DIELECTRIC_F0 * (1.0 - metallic) + base * metallic;
+        let fresnel = f0 + (WHITE - f0) * (1.0 - v_dot_h).powi(5);
+        let specular = fresnel
+            * (ggx_distribution(n_dot_h, roughness) * smith_geometry(n_dot_v, n_dot_l, roughness)
+                / (4.0 * n_dot_v * n_dot_l));
+        let diffuse = (WHITE - fresnel) * base * ((1.0 - metallic) / PI);
+        // light intensity is the irradiance on a surface facing the light, hence the factor
+        // of pi that shows a white lambertian surface facing the light at its intensity
+        (diffuse + specular) * light.intensity_at(point) * (PI * n_dot_l)
+    }
+}
+
+impl Default for CookTorrance {
+    fn default() -> Self {
+        CookTorrance::new(WHITE, 0.0, 0.5, BLACK)
+    }
+}
+
+fn scalar(value: f64) -> Pattern {
+    Pattern::from(Color::new(value, value, value))
+}
+
+fn mean(color: Color) -> f64 {
+    (color.rgb.x + color.rgb.y + color.rgb.z) / 3.0
+}
+
+// GGX / Trowbridge-Reitz normal distribution, with alpha = roughness squared
+fn ggx_distribution(n_dot_h: f64, roughness: f64) -> f64 {
+    let a2 = roughness.powi(4);
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+// Smith shadowing-masking with the Schlick-GGX approximation for direct light
+fn smith_geometry(n_dot_v: f64, n_dot_l: f64, roughness: f64) -> f64 {
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    let g1 = |x: f64| x / (x * (1.0 - k) + k);
+    g1(n_dot_v) * g1(n_dot_l)
+}
+
+#[cfg(test)]
+mod cook_torrance_tests {
+    use super::*;
+    use crate::features::{pattern::stripe::Stripe, shape::sphere::Sphere};
+
+    fn facing_light(model: &CookTorrance, in_shadow: bool) -> Color {
+        let light = Light::new(Point::new(0.0, 0.0, -10.0), WHITE);
+        let sphere = Sphere::default();
+        let emitted = model.emitted(&sphere, &Point::default());
+        if in_shadow {
+            return emitted;
+        }
+        emitted
+            + model.direct(
+                &light,
+                &sphere,
+                &Point::default(),
+                &Vector::new(0.0, 0.0, -1.0),
+                &Vector::new(0.0, 0.0, -1.0),
+            )
+    }
+
+    #[test]
+    fn rough_dielectric_is_close_to_lambert() {
+        let model = CookTorrance::new(WHITE, 0.0, 1.0, BLACK);
+        let c = facing_light(&model, false);
+        assert!((c.rgb.x - 1.0).abs() < 0.05, "{:?}", c);
+    }
+
+    #[test]
+    fn metals_tint_their_highlight_and_have_no_diffuse() {
+        let gold = Color::new(1.0, 0.8, 0.3);
+        let model = CookTorrance::new(gold, 1.0, 0.3, BLACK);
+        let c = facing_light(&model, false);
+        assert!(c.rgb.x > c.rgb.y && c.rgb.y > c.rgb.z, "{:?}", c);
+        // seen from the side the highlight is gone and nothing diffuse is left
+        let light = Light::new(Point::new(0.0, 0.0, -10.0), WHITE);
+        let grazing = model.direct(
+            &light,
+            &Sphere::default(),
+            &Point::default(),
+            &Vector::new(0.0, 0.9, -(0.19_f64).sqrt()),
+            &Vector::new(0.0, 0.0, -1.0),
+        );
+        assert!(grazing.rgb.x < 0.05, "{:?}", grazing);
+    }
+
+    #[test]
+    fn smoother_surfaces_have_sharper_highlights() {
+        let rough = facing_light(&CookTorrance::new(BLACK, 0.0, 0.8, BLACK), false);
+        let smooth = facing_light(&CookTorrance::new(BLACK, 0.0, 0.2, BLACK), false);
+        assert!(smooth.rgb.x > rough.rgb.x);
+    }
+
+    #[test]
+    fn shadowed_surfaces_keep_emission() {
+        let glow = Color::new(0.2, 0.4, 0.0);
+        let model = CookTorrance::new(WHITE, 0.0, 0.5, glow);
+        assert_eq!(facing_light(&model, true), glow);
+    }
+
+    #[test]
+    fn parameters_follow_patterns() {
+        let mut striped = CookTorrance::new(Color::new(0.8, 0.2, 0.2), 0.0, 0.5, BLACK);
+        striped.metallic = Pattern::Stripe(Stripe::default());
+        let metal = CookTorrance {
+            metallic: scalar(1.0),
+            ..striped.clone()
+        };
+        let plastic = CookTorrance {
+            metallic: scalar(0.0),
+            ..striped.clone()
+        };
+        let light = Light::new(Point::new(0.0, 0.0, -10.0), WHITE);
+        let lit = |model: &CookTorrance, x: f64| {
+            model.direct(
+                &light,
+                &Sphere::default(),
+                &Point::new(x, 0.0, 0.0),
+                &Vector::new(0.0, 0.0, -1.0),
+                &Vector::new(0.0, 0.0, -1.0),
+            )
+        };
+        assert_eq!(lit(&striped, 0.5), lit(&metal, 0.5));
+        assert_eq!(lit(&striped, 1.5), lit(&plastic, 1.5));
+        assert_ne!(lit(&metal, 0.5), lit(&plastic, 0.5));
+    }
+}
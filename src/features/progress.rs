@@ -0,0 +1,117 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+// told how a render is getting on; renders call it from their worker threads once per
+// finished tile, so implementations should be cheap
+pub trait ProgressObserver: Sync + Send {
+    // the number of pixel samples the render will take, counting each progressive pass
+    fn start(&self, _total: u64) {}
+    fn advance(&self, done: u64);
+    fn finish(&self) {}
+}
+
+// reports nothing
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Silent;
+
+impl ProgressObserver for Silent {
+    fn advance(&self, _done: u64) {}
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ProgressUpdate {
+    Started { total: u64 },
+    Advanced { done: u64 },
+    Finished,
+}
+
+// forwards every update to a receiver, typically on a UI thread; updates are dropped
+// once the receiver is gone
+#[derive(Debug, Clone)]
+pub struct ChannelProgress {
+    sender: Sender<ProgressUpdate>,
+}
+
+impl ChannelProgress {
+    pub fn new() -> (Self, Receiver<ProgressUpdate>) {
+        let (sender, receiver) = channel();
+        (ChannelProgress { sender }, receiver)
+    }
+}
+
+impl ProgressObserver for ChannelProgress {
+    fn start(&self, total: u64) {
+        let _ = self.sender.send(ProgressUpdate::Started { total });
+    }
+    fn advance(&self, done: u64) {
+        let _ = self.sender.send(ProgressUpdate::Advanced { done });
+    }
+    fn finish(&self) {
+        let _ = self.sender.send(ProgressUpdate::Finished);
+    }
+}
+
+// a terminal progress bar
+#[cfg(feature = "indicatif")]
+#[derive(Debug, Clone)]
+pub struct ProgressBar {
+    bar: indicatif::ProgressBar,
+}
+
+#[cfg(feature = "indicatif")]
+impl ProgressBar {
+    pub fn new() -> Self {
+        ProgressBar {
+            bar: indicatif::ProgressBar::new(0),
+        }
+    }
+}
+
+#[cfg(feature = "indicatif")]
+impl Default for ProgressBar {
+    fn default() -> Self {
+        ProgressBar::new()
+    }
+}
+
+#[cfg(feature = "indicatif")]
+impl ProgressObserver for ProgressBar {
+    fn start(&self, total: u64) {
+        self.bar.set_length(total);
+        self.bar.reset();
+    }
+    fn advance(&self, done: u64) {
+        self.bar.inc(done);
+    }
+    fn finish(&self) {
+        self.bar.finish();
+    }
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+
+    #[test]
+    fn channels_forward_updates() {
+        let (observer, updates) = ChannelProgress::new();
+        observer.start(10);
+        observer.advance(4);
+        observer.finish();
+        drop(observer);
+        assert_eq!(
+            updates.iter().collect::<Vec<_>>(),
+            vec![
+                ProgressUpdate::Started { total: 10 },
+                ProgressUpdate::Advanced { done: 4 },
+                ProgressUpdate::Finished
+            ]
+        );
+    }
+
+    #[test]
+    fn a_dropped_receiver_is_ignored() {
+        let (observer, updates) = ChannelProgress::new();
+        drop(updates);
+        observer.advance(1);
+    }
+}
@@ -1,20 +1,31 @@
 use super::{matrice::Matrice, point::Point, vector::Vector};
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
+    // when in the shutter interval the ray was cast, from 0 when it opens to 1 when it
+    // closes; moving shapes are met where they stand at that time
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Self {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+        }
+    }
+    pub fn with_time(self, time: f64) -> Self {
+        Ray { time, ..self }
     }
     pub fn position(&self, t: f64) -> Point {
         self.origin + self.direction * t
     }
 
     pub fn transform(&self, m: &Matrice) -> Self {
-        Ray::new(m.clone() * self.origin, m.clone() * self.direction)
+        Ray::new(m.clone() * self.origin, m.clone() * self.direction).with_time(self.time)
     }
 }
 
@@ -41,4 +52,10 @@ mod ray_transformation_tests {
         assert_eq!(r2.origin, Point::new(2.0, 6.0, 12.0));
         assert_eq!(r2.direction, Vector::new(0.0, 3.0, 0.0));
     }
+
+    #[test]
+    fn transformed_rays_keep_their_time() {
+        let r = Ray::new(Point::new(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0)).with_time(0.4);
+        assert_eq!(r.transform(&translation(3.0, 4.0, 5.0)).time, 0.4);
+    }
 }
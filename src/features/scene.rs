@@ -0,0 +1,1241 @@
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{
+    animation::{Animation, CameraPath, Interpolation, Keyable, ShapeTracks, Track},
+    background::{Background, Environment},
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    consts::{BLACK, WHITE},
+    error::{Error, Result},
+    fog::Fog,
+    integrator::{Integrator, PathTracer},
+    light::Light,
+    material::{Material, Model},
+    matrice::Matrice,
+    normal::{bump::Bump, height_map::HeightMap, normal_map::NormalMap, NormalModifier},
+    occlusion::AmbientOcclusion,
+    pattern::{
+        blend::Blend,
+        checker::Checker,
+        gradient::Gradient,
+        perturbed::Perturbed,
+        radial_gradient::RadialGradient,
+        ring::Ring,
+        stripe::Stripe,
+        texture_map::{CubeMap, TextureMap},
+        uv::{AlignCheck, UvCheckers, UvImage, UvMapping, UvPattern},
+        Pattern,
+    },
+    pbr::CookTorrance,
+    point::Point,
+    shape::{plane::Plane, sphere::Sphere, volume::Volume, Shape},
+    transformations::{
+        rotation_x, rotation_y, rotation_z, scaling, shearing, translation, view_transformation,
+    },
+    vector::Vector,
+    world::World,
+};
+
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+    pub animation: Animation,
+    // the files the scene loaded, such as images, in path order
+    pub files: Vec<PathBuf>,
+}
+
+impl Scene {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Scene::parse_at(&fs::read_to_string(path)?, path)
+    }
+    // parses a scene read from elsewhere as if it came from path, so the images it
+    // names are found next to it
+    pub fn parse_at<P: AsRef<Path>>(source: &str, path: P) -> Result<Self> {
+        Parser::at(path.as_ref()).parse(source)
+    }
+    // like parse_at, but the files the scene loads come from files, under the paths
+    // they would be read from, rather than from the disk
+    pub fn parse_with_files<P: AsRef<Path>>(
+        source: &str,
+        path: P,
+        files: &[(PathBuf, Vec<u8>)],
+    ) -> Result<Self> {
+        let parser = Parser {
+            sent: Some(files),
+            ..Parser::at(path.as_ref())
+        };
+        parser.parse(source)
+    }
+    pub fn parse(source: &str) -> Result<Self> {
+        Parser::default().parse(source)
+    }
+}
+
+#[derive(Default)]
+struct Parser<'a> {
+    base: PathBuf,
+    // the files to load instead of reading the disk, when the scene came with them
+    sent: Option<&'a [(PathBuf, Vec<u8>)]>,
+    camera: Option<Camera>,
+    light: Option<Light>,
+    patterns: HashMap<String, Pattern>,
+    materials: HashMap<String, Material>,
+    normals: HashMap<String, NormalModifier>,
+    images: HashMap<PathBuf, Arc<Canvas>>,
+    shapes: Vec<Box<dyn Shape>>,
+    light_samples: Option<usize>,
+    ambient_occlusion: Option<AmbientOcclusion>,
+    background: Option<Background>,
+    fog: Option<Fog>,
+    // shape names given in the scene, to the ids of the shapes
+    names: HashMap<String, String>,
+    // the camera's from, to and up, which camera paths start out from
+    view: Option<(Point, Point, Vector)>,
+    animation: Animation,
+}
+
+impl Parser<'_> {
+    fn at(path: &Path) -> Self {
+        Parser {
+            base: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn parse(mut self, source: &str) -> Result<Scene> {
+        for (ix, line) in source.lines().enumerate() {
+            self.line(line).map_err(|e| e.at_line(ix + 1))?;
+        }
+        self.finish()
+    }
+
+    fn line(&mut self, line: &str) -> Result<()> {
+        let line = match line.find('#') {
+            Some(ix) => &line[..ix],
+            None => line,
+        };
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) => k,
+            None => return Ok(()),
+        };
+        let tokens: Vec<&str> = tokens.collect();
+        match keyword {
+            "camera" => self.camera(&pairs(&tokens)?),
+            "light" => self.light(&pairs(&tokens)?),
+            "world" => self.world(&pairs(&tokens)?),
+            "background" => self.background(&tokens),
+            "pattern" => self.pattern(&tokens),
+            "normal" => self.normal(&tokens),
+            "material" => self.material(&tokens),
+            "sphere" => self.shape(Box::<Sphere>::default(), &pairs(&tokens)?),
+            "plane" => self.shape(Box::<Plane>::default(), &pairs(&tokens)?),
+            "volume" => self.volume(&tokens),
+            "animate" => self.animate(&tokens),
+            _ => Err(Error::parse(format!("unknown directive '{}'", keyword))),
+        }
+    }
+
+    fn finish(self) -> Result<Scene> {
+        let camera = self
+            .camera
+            .ok_or_else(|| Error::parse("scene has no camera"))?;
+        // glowing shapes can light a scene on their own
+        let emissive = self.shapes.iter().any(|s| s.get_material().is_emissive());
+        let light = match self.light {
+            Some(light) => light,
+            None if emissive => Light::new(Point::new(0.0, 0.0, 0.0), BLACK),
+            None => return Err(Error::parse("scene has no light")),
+        };
+        let mut world = World::new(light);
+        if let Some(samples) = self.light_samples {
+            world.light_samples = samples;
+        }
+        world.ambient_occlusion = self.ambient_occlusion;
+        world.background = self.background.unwrap_or_default();
+        world.fog = self.fog;
+        world.add_shapes(self.shapes);
+        let mut files: Vec<PathBuf> = self.images.into_keys().collect();
+        files.sort();
+        Ok(Scene {
+            world,
+            camera,
+            animation: self.animation,
+            files,
+        })
+    }
+
+    fn camera(&mut self, pairs: &[(&str, &str)]) -> Result<()> {
+        if self.camera.is_some() {
+            return Err(Error::parse("camera defined more than once"));
+        }
+        let mut width = 100.0;
+        let mut height = 100.0;
+        let mut fov = 60.0;
+        let mut samples = 1;
+        let mut seed = 0;
+        let mut integrator = None;
+        let mut tracer = PathTracer::default();
+        let mut ao = AmbientOcclusion::default();
+        let mut from = Point::new(0.0, 0.0, 0.0);
+        let mut to = Point::new(0.0, 0.0, -1.0);
+        let mut up = Vector::new(0.0, 1.0, 0.0);
+        for (key, value) in pairs {
+            match *key {
+                "width" => width = parse_usize(value)? as f64,
+                "height" => height = parse_usize(value)? as f64,
+                "fov" => fov = parse_f64(value)?,
+                "samples" => samples = parse_usize(value)?,
+                "from" => from = parse_point(value)?,
+                "to" => to = parse_point(value)?,
+                "up" => up = parse_vector(value)?,
+                "integrator" => integrator = Some(*value),
+                "max-depth" => tracer.max_depth = parse_usize(value)?,
+                "roulette-depth" => tracer.roulette_depth = parse_usize(value)?,
+                "seed" => seed = parse_u64(value)?,
+                "ao-samples" => ao.samples = parse_usize(value)?,
+                "ao-distance" => ao.distance = parse_f64(value)?,
+                _ => return Err(Error::parse(format!("unknown camera attribute '{}'", key))),
+            }
+        }
+        if width < 1.0 || height < 1.0 {
+            return Err(Error::parse("camera width and height must be positive"));
+        }
+        let mut camera = Camera::new(width, height, fov.to_radians());
+        camera
+            .set_transform(view_transformation(from, to, up))
+            .map_err(located)?;
+        camera.samples = samples;
+        camera.seed = seed;
+        camera.integrator = match integrator {
+            None | Some("whitted") => Integrator::Whitted,
+            Some("path") => Integrator::PathTracer(tracer),
+            Some("ao") => Integrator::AmbientOcclusion(ao),
+            Some(other) => return Err(Error::parse(format!("unknown integrator '{}'", other))),
+        };
+        self.camera = Some(camera);
+        self.view = Some((from, to, up));
+        Ok(())
+    }
+
+    fn light(&mut self, pairs: &[(&str, &str)]) -> Result<()> {
+        if self.light.is_some() {
+            return Err(Error::parse("only one light is supported"));
+        }
+        let mut position = Point::new(0.0, 0.0, 0.0);
+        let mut intensity = WHITE;
+        let mut to = None;
+        let mut angle = 30.0;
+        let mut inner = None;
+        for (key, value) in pairs {
+            match *key {
+                "position" => position = parse_point(value)?,
+                "intensity" => intensity = parse_color(value)?,
+                "to" => to = Some(parse_point(value)?),
+                "angle" => angle = parse_f64(value)?,
+                "inner" => inner = Some(parse_f64(value)?),
+                _ => return Err(Error::parse(format!("unknown light attribute '{}'", key))),
+            }
+        }
+        // pointing a light somewhere turns it into a spot light
+        self.light = Some(match to {
+            Some(to) => Light::spot(
+                position,
+                intensity,
+                to - position,
+                inner.unwrap_or(angle).to_radians(),
+                angle.to_radians(),
+            ),
+            None => Light::new(position, intensity),
+        });
+        Ok(())
+    }
+
+    fn world(&mut self, pairs: &[(&str, &str)]) -> Result<()> {
+        for (key, value) in pairs {
+            match *key {
+                "light-samples" => self.light_samples = Some(parse_usize(value)?),
+                "ao-samples" => {
+                    self.ambient_occlusion
+                        .get_or_insert_with(Default::default)
+                        .samples = parse_usize(value)?
+                }
+                "ao-distance" => {
+                    self.ambient_occlusion
+                        .get_or_insert_with(Default::default)
+                        .distance = parse_f64(value)?
+                }
+                "fog-color" => self.fog.get_or_insert_with(default_fog).color = parse_color(value)?,
+                "fog-density" => {
+                    self.fog.get_or_insert_with(default_fog).density = parse_f64(value)?
+                }
+                _ => return Err(Error::parse(format!("unknown world attribute '{}'", key))),
+            }
+        }
+        Ok(())
+    }
+
+    fn background(&mut self, tokens: &[&str]) -> Result<()> {
+        if self.background.is_some() {
+            return Err(Error::parse("background defined more than once"));
+        }
+        let (kind, rest) = match tokens {
+            [kind, rest @ ..] => (*kind, rest),
+            _ => return Err(Error::parse("expected 'background <kind> ...'")),
+        };
+        let mut color = BLACK;
+        let mut horizon = WHITE;
+        let mut zenith = Color::new(0.5, 0.7, 1.0);
+        let mut image = None;
+        let mut rotation = 0.0;
+        let mut strength = 1.0;
+        for (key, value) in pairs(rest)? {
+            match key {
+                "color" => color = parse_color(value)?,
+                "horizon" => horizon = parse_color(value)?,
+                "zenith" => zenith = parse_color(value)?,
+                "image" => image = Some(self.image(value)?),
+                "rotate" => rotation = parse_f64(value)? * PI / 180.0,
+                "strength" => strength = parse_f64(value)?,
+                _ => {
+                    return Err(Error::parse(format!(
+                        "unknown background attribute '{}'",
+                        key
+                    )))
+                }
+            }
+        }
+        self.background = Some(match kind {
+            "solid" => Background::Solid(color),
+            "sky" => Background::Sky { horizon, zenith },
+            "environment" => {
+                let image = image.ok_or_else(|| Error::parse("environment needs an image"))?;
+                Background::Environment(Environment::new(image, rotation, strength))
+            }
+            _ => return Err(Error::parse(format!("unknown background kind '{}'", kind))),
+        });
+        Ok(())
+    }
+
+    fn pattern(&mut self, tokens: &[&str]) -> Result<()> {
+        let (name, kind, rest) = match tokens {
+            [name, kind, rest @ ..] => (*name, *kind, rest),
+            _ => return Err(Error::parse("expected 'pattern <name> <kind> ...'")),
+        };
+        let mut a = Pattern::from(WHITE);
+        let mut b = Pattern::from(BLACK);
+        let mut inner = None;
+        let mut amount = 0.1;
+        let mut octaves = 3;
+        let mut offset = None;
+        let mut mapping = UvMapping::Spherical;
+        let mut uv = None;
+        let mut uv_size = (2.0, 2.0);
+        let mut faces: [Option<UvPattern>; 6] = Default::default();
+        let mut transforms = vec![];
+        for (key, value) in pairs(rest)? {
+            match key {
+                "a" | "color" => a = self.pattern_or_color(value)?,
+                "b" => b = self.pattern_or_color(value)?,
+                "pattern" => inner = Some(self.named_pattern(value)?),
+                "amount" => amount = parse_f64(value)?,
+                "octaves" => octaves = parse_usize(value)?,
+                "offset" => offset = Some(parse_f64(value)?),
+                "mapping" => mapping = parse_mapping(value)?,
+                "uv" => uv = Some(value),
+                "width" => uv_size.0 = parse_f64(value)?,
+                "height" => uv_size.1 = parse_f64(value)?,
+                "left" => faces[0] = Some(self.face(value)?),
+                "front" => faces[1] = Some(self.face(value)?),
+                "right" => faces[2] = Some(self.face(value)?),
+                "back" => faces[3] = Some(self.face(value)?),
+                "up" => faces[4] = Some(self.face(value)?),
+                "down" => faces[5] = Some(self.face(value)?),
+                _ => transforms.push(transform(key, value)?),
+            }
+        }
+        let transform = compose(transforms);
+        let pattern = match kind {
+            "solid" => Pattern::from(solid_color(&a)?),
+            "stripe" => Pattern::Stripe(Stripe::new(a, b, transform).map_err(located)?),
+            "checker" => {
+                let mut checker = Checker::new(a, b, transform).map_err(located)?;
+                checker.set_offset(offset.unwrap_or(0.0));
+                Pattern::Checker(checker)
+            }
+            "safe-checker" => {
+                let mut checker = Checker::acne_resistant(a, b, transform).map_err(located)?;
+                if let Some(offset) = offset {
+                    checker.set_offset(offset);
+                }
+                Pattern::Checker(checker)
+            }
+            "ring" => Pattern::Ring(Ring::new(a, b, transform).map_err(located)?),
+            "gradient" => Pattern::Gradient(Gradient::new(a, b, transform).map_err(located)?),
+            "radial-gradient" => {
+                Pattern::RadialGradient(RadialGradient::new(a, b, transform).map_err(located)?)
+            }
+            "blend" => Pattern::Blend(Blend::new(a, b, transform).map_err(located)?),
+            "perturb" => {
+                let inner = inner.ok_or_else(|| Error::parse("perturb needs a pattern"))?;
+                Pattern::Perturbed(
+                    Perturbed::new(inner, amount, octaves, transform).map_err(located)?,
+                )
+            }
+            "texture" => {
+                let uv_pattern = match uv.unwrap_or("checkers") {
+                    "checkers" => UvPattern::Checkers(UvCheckers::new(
+                        uv_size.0,
+                        uv_size.1,
+                        solid_color(&a)?,
+                        solid_color(&b)?,
+                    )),
+                    image => self.face(image)?,
+                };
+                Pattern::TextureMap(
+                    TextureMap::new(mapping, uv_pattern, transform).map_err(located)?,
+                )
+            }
+            "cube-map" => {
+                let faces = faces
+                    .map(|f| f.unwrap_or_else(|| UvPattern::AlignCheck(AlignCheck::default())));
+                Pattern::CubeMap(CubeMap::new(faces, transform).map_err(located)?)
+            }
+            _ => return Err(Error::parse(format!("unknown pattern kind '{}'", kind))),
+        };
+        self.patterns.insert(name.to_string(), pattern);
+        Ok(())
+    }
+
+    // a uv pattern is either the align-check debug pattern or a ppm image
+    fn face(&mut self, value: &str) -> Result<UvPattern> {
+        if value == "align-check" {
+            return Ok(UvPattern::AlignCheck(AlignCheck::default()));
+        }
+        Ok(UvPattern::Image(self.uv_image(value)?))
+    }
+
+    fn uv_image(&mut self, value: &str) -> Result<UvImage> {
+        Ok(UvImage::new(self.image(value)?))
+    }
+
+    // images are found relative to the scene file and loaded once however often they are used
+    fn image(&mut self, value: &str) -> Result<Arc<Canvas>> {
+        let path = self.base.join(value);
+        let canvas = match self.images.get(&path) {
+            Some(canvas) => canvas.clone(),
+            None => {
+                let canvas = match self.sent {
+                    Some(files) => files
+                        .iter()
+                        .find(|(sent, _)| *sent == path)
+                        .ok_or_else(|| Error::parse("not among the files sent with the scene"))
+                        .and_then(|(_, data)| Canvas::decode(data)),
+                    None => Canvas::load(&path),
+                };
+                let canvas = Arc::new(
+                    canvas.map_err(|e| Error::parse(format!("{}: {}", path.display(), e)))?,
+                );
+                self.images.insert(path, canvas.clone());
+                canvas
+            }
+        };
+        Ok(canvas)
+    }
+
+    fn normal(&mut self, tokens: &[&str]) -> Result<()> {
+        let (name, kind, rest) = match tokens {
+            [name, kind, rest @ ..] => (*name, *kind, rest),
+            _ => return Err(Error::parse("expected 'normal <name> <kind> ...'")),
+        };
+        let mut amount = 1.0;
+        let mut octaves = 3;
+        let mut mapping = UvMapping::Planar;
+        let mut image = None;
+        let mut transforms = vec![];
+        for (key, value) in pairs(rest)? {
+            match key {
+                "amount" => amount = parse_f64(value)?,
+                "octaves" => octaves = parse_usize(value)?,
+                "mapping" => mapping = parse_mapping(value)?,
+                "image" => image = Some(self.uv_image(value)?),
+                _ => transforms.push(transform(key, value)?),
+            }
+        }
+        let transform = compose(transforms);
+        let mut needs_image = || {
+            image
+                .take()
+                .ok_or_else(|| Error::parse(format!("{} needs an image", kind)))
+        };
+        let modifier = match kind {
+            "bump" => NormalModifier::Bump(Bump::new(amount, octaves, transform).map_err(located)?),
+            "height-map" => NormalModifier::HeightMap(
+                HeightMap::new(mapping, needs_image()?, amount, transform).map_err(located)?,
+            ),
+            "normal-map" => NormalModifier::NormalMap(
+                NormalMap::new(mapping, needs_image()?, transform).map_err(located)?,
+            ),
+            _ => return Err(Error::parse(format!("unknown normal kind '{}'", kind))),
+        };
+        self.normals.insert(name.to_string(), modifier);
+        Ok(())
+    }
+
+    // pattern colors can be given inline as r,g,b or as the name of another pattern
+    fn pattern_or_color(&self, value: &str) -> Result<Pattern> {
+        if value.contains(',') {
+            return Ok(Pattern::from(parse_color(value)?));
+        }
+        self.named_pattern(value)
+    }
+
+    // scalar parameters are a plain number or a pattern read as gray
+    fn scalar(&self, value: &str) -> Result<Pattern> {
+        match value.parse::<f64>() {
+            Ok(v) => Ok(Pattern::from(Color::new(v, v, v))),
+            Err(_) => self.named_pattern(value),
+        }
+    }
+
+    fn named_pattern(&self, name: &str) -> Result<Pattern> {
+        self.patterns
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::parse(format!("unknown pattern '{}'", name)))
+    }
+
+    fn material(&mut self, tokens: &[&str]) -> Result<()> {
+        let (name, rest) = match tokens {
+            [name, rest @ ..] => (*name, rest),
+            _ => return Err(Error::parse("expected 'material <name> ...'")),
+        };
+        let mut material = Material::default();
+        let mut pbr: Option<CookTorrance> = None;
+        let mut base_color = None;
+        for (key, value) in pairs(rest)? {
+            match key {
+                "model" => match value {
+                    "phong" => pbr = None,
+                    "pbr" => pbr = Some(pbr.unwrap_or_default()),
+                    _ => return Err(Error::parse(format!("unknown material model '{}'", value))),
+                },
+                "emission" => material.emission = parse_color(value)?,
+                "emission-strength" => material.emission_strength = parse_f64(value)?,
+                "base-color" => base_color = Some(self.pattern_or_color(value)?),
+                "metallic" => {
+                    pbr.get_or_insert_with(Default::default).metallic = self.scalar(value)?
+                }
+                "roughness" => {
+                    pbr.get_or_insert_with(Default::default).roughness = self.scalar(value)?
+                }
+                "emissive" => {
+                    pbr.get_or_insert_with(Default::default).emissive =
+                        self.pattern_or_color(value)?
+                }
+                "color" => material.color = parse_color(value)?,
+                "ambient" => material.ambient = parse_f64(value)?,
+                "diffuse" => material.diffuse = parse_f64(value)?,
+                "specular" => material.specular = parse_f64(value)?,
+                "shininess" => material.shininess = parse_f64(value)?,
+                "pattern" => material.pattern = Some(self.named_pattern(value)?),
+                "normal" => {
+                    let modifier = self
+                        .normals
+                        .get(value)
+                        .ok_or_else(|| Error::parse(format!("unknown normal '{}'", value)))?;
+                    material.normal = Some(modifier.clone());
+                }
+                _ => {
+                    return Err(Error::parse(format!(
+                        "unknown material attribute '{}'",
+                        key
+                    )))
+                }
+            }
+        }
+        // the pbr base color falls back to the phong color or pattern
+        if let Some(mut pbr) = pbr {
+            pbr.base_color = base_color
+                .or_else(|| material.pattern.clone())
+                .unwrap_or_else(|| material.color.into());
+            material.model = Model::CookTorrance(Box::new(pbr));
+        } else if base_color.is_some() {
+            return Err(Error::parse("base-color needs model=pbr"));
+        }
+        material.validate().map_err(located)?;
+        self.materials.insert(name.to_string(), material);
+        Ok(())
+    }
+
+    // a volume fills a boundary shape, taking the material and transforms of the shape
+    fn volume(&mut self, tokens: &[&str]) -> Result<()> {
+        let (kind, rest) = match tokens {
+            [kind, rest @ ..] => (*kind, rest),
+            _ => return Err(Error::parse("expected 'volume <shape> ...'")),
+        };
+        let boundary: Box<dyn Shape> = match kind {
+            "sphere" => Box::<Sphere>::default(),
+            _ => return Err(Error::parse(format!("unknown volume boundary '{}'", kind))),
+        };
+        let mut density = 1.0;
+        let mut others = vec![];
+        for (key, value) in pairs(rest)? {
+            match key {
+                "density" => density = parse_f64(value)?,
+                _ => others.push((key, value)),
+            }
+        }
+        if density <= 0.0 || !density.is_finite() {
+            return Err(Error::parse("volume density must be positive"));
+        }
+        let volume = Volume::new(boundary, density, Material::default()).map_err(located)?;
+        self.shape(Box::new(volume), &others)
+    }
+
+    fn animate(&mut self, tokens: &[&str]) -> Result<()> {
+        let (target, property, keys) = match tokens {
+            [target, property, keys @ ..] => (*target, *property, keys),
+            _ => {
+                return Err(Error::parse(
+                    "expected 'animate <target> <property> <time>:<value> ...'",
+                ))
+            }
+        };
+        let (interpolation, keys) = match keys {
+            ["spline", keys @ ..] => (Interpolation::Spline, keys),
+            ["linear", keys @ ..] => (Interpolation::Linear, keys),
+            keys => (Interpolation::Linear, keys),
+        };
+        let unknown = || -> Result<()> {
+            Err(Error::parse(format!(
+                "cannot animate the {} of {}",
+                property, target
+            )))
+        };
+        match target {
+            "camera" => {
+                let (from, to, up) = self
+                    .view
+                    .ok_or_else(|| Error::parse("animate camera needs a camera first"))?;
+                let path = self.animation.camera.get_or_insert_with(|| CameraPath {
+                    from: Track::constant(from),
+                    to: Track::constant(to),
+                    up: Track::constant(up),
+                });
+                match property {
+                    "from" => path.from = track(interpolation, keys, parse_point)?,
+                    "to" => path.to = track(interpolation, keys, parse_point)?,
+                    "up" => path.up = track(interpolation, keys, parse_vector)?,
+                    _ => return unknown(),
+                }
+            }
+            "light" => {
+                let light = &mut self.animation.light;
+                match property {
+                    "position" => light.position = Some(track(interpolation, keys, parse_point)?),
+                    "intensity" => light.intensity = Some(track(interpolation, keys, parse_color)?),
+                    _ => return unknown(),
+                }
+            }
+            name => {
+                let id = self
+                    .names
+                    .get(name)
+                    .ok_or_else(|| Error::parse(format!("unknown shape '{}'", name)))?;
+                let shape = self.shapes.iter().find(|s| s.get_shape_id() == *id);
+                let base = shape
+                    .map(|s| s.get_transform())
+                    .unwrap_or_else(|| Matrice::identity_matrix(4));
+                let tracks = self
+                    .animation
+                    .shapes
+                    .entry(id.clone())
+                    .or_insert_with(|| ShapeTracks::new(base));
+                match property {
+                    "translate" => {
+                        tracks.translate = Some(track(interpolation, keys, parse_vector)?)
+                    }
+                    "rotate" => tracks.rotate = Some(track(interpolation, keys, parse_degrees)?),
+                    "scale" => tracks.scale = Some(track(interpolation, keys, parse_vector)?),
+                    "color" => tracks.color = Some(track(interpolation, keys, parse_color)?),
+                    "ambient" => tracks.ambient = Some(track(interpolation, keys, parse_f64)?),
+                    "diffuse" => tracks.diffuse = Some(track(interpolation, keys, parse_f64)?),
+                    "specular" => tracks.specular = Some(track(interpolation, keys, parse_f64)?),
+                    "shininess" => tracks.shininess = Some(track(interpolation, keys, parse_f64)?),
+                    _ => return unknown(),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn shape(&mut self, mut shape: Box<dyn Shape>, pairs: &[(&str, &str)]) -> Result<()> {
+        let mut transforms = vec![];
+        for (key, value) in pairs {
+            match *key {
+                "name" => {
+                    if ["camera", "light"].contains(value) || self.names.contains_key(*value) {
+                        return Err(Error::parse(format!("shape name '{}' is taken", value)));
+                    }
+                    self.names.insert(value.to_string(), shape.get_shape_id());
+                }
+                "material" => {
+                    let material = self
+                        .materials
+                        .get(*value)
+                        .ok_or_else(|| Error::parse(format!("unknown material '{}'", value)))?;
+                    shape.set_material(material.clone()).map_err(located)?;
+                }
+                _ => transforms.push(transform(key, value)?),
+            }
+        }
+        shape.set_transform(compose(transforms)).map_err(located)?;
+        self.shapes.push(shape);
+        Ok(())
+    }
+}
+
+// a track from <time>:<value> keys
+fn track<T: Keyable>(
+    interpolation: Interpolation,
+    keys: &[&str],
+    parse: impl Fn(&str) -> Result<T>,
+) -> Result<Track<T>> {
+    if keys.is_empty() {
+        return Err(Error::parse("expected at least one <time>:<value> key"));
+    }
+    let mut track = Track::new(interpolation);
+    for key in keys {
+        let (time, value) = key
+            .split_once(':')
+            .ok_or_else(|| Error::parse(format!("expected <time>:<value>, found '{}'", key)))?;
+        track.insert(parse_f64(time)?, parse(value)?);
+    }
+    Ok(track)
+}
+fn parse_degrees(value: &str) -> Result<Vector> {
+    Ok(parse_vector(value)? * (PI / 180.0))
+}
+fn pairs<'a>(tokens: &[&'a str]) -> Result<Vec<(&'a str, &'a str)>> {
+    tokens
+        .iter()
+        .map(|t| {
+            t.split_once('=')
+                .ok_or_else(|| Error::parse(format!("expected key=value, found '{}'", t)))
+        })
+        .collect()
+}
+
+fn transform(key: &str, value: &str) -> Result<Matrice> {
+    match key {
+        "translate" => {
+            let [x, y, z] = parse_triple(value)?;
+            Ok(translation(x, y, z))
+        }
+        "scale" => {
+            let [x, y, z] = parse_triple(value)?;
+            Ok(scaling(x, y, z))
+        }
+        "rotate-x" => Ok(rotation_x(parse_f64(value)? * PI / 180.0)),
+        "rotate-y" => Ok(rotation_y(parse_f64(value)? * PI / 180.0)),
+        "rotate-z" => Ok(rotation_z(parse_f64(value)? * PI / 180.0)),
+        "shear" => {
+            let v = parse_list(value)?;
+            if v.len() != 6 {
+                return Err(Error::parse(format!(
+                    "shear expects 6 values, found {}",
+                    v.len()
+                )));
+            }
+            Ok(shearing(v[0], v[1], v[2], v[3], v[4], v[5]))
+        }
+        _ => Err(Error::parse(format!("unknown attribute '{}'", key))),
+    }
+}
+
+// errors from building scene objects are reported against the line that defined them
+fn located(e: Error) -> Error {
+    Error::parse(e.to_string())
+}
+
+// transforms are listed in the order they are applied to the object
+fn compose(transforms: Vec<Matrice>) -> Matrice {
+    transforms
+        .into_iter()
+        .fold(Matrice::identity_matrix(4), |acc, t| t * acc)
+}
+
+fn default_fog() -> Fog {
+    Fog::new(Color::new(0.5, 0.5, 0.5), 0.1)
+}
+
+fn parse_mapping(value: &str) -> Result<UvMapping> {
+    match value {
+        "spherical" => Ok(UvMapping::Spherical),
+        "planar" => Ok(UvMapping::Planar),
+        "cylindrical" => Ok(UvMapping::Cylindrical),
+        _ => Err(Error::parse(format!("unknown uv mapping '{}'", value))),
+    }
+}
+
+fn solid_color(pattern: &Pattern) -> Result<Color> {
+    match pattern {
+        Pattern::Solid(s) => Ok(s.at()),
+        _ => Err(Error::parse("expected a color, found a pattern")),
+    }
+}
+
+fn parse_f64(value: &str) -> Result<f64> {
+    value
+        .parse()
+        .map_err(|_| Error::parse(format!("invalid number '{}'", value)))
+}
+
+fn parse_usize(value: &str) -> Result<usize> {
+    value
+        .parse()
+        .map_err(|_| Error::parse(format!("invalid integer '{}'", value)))
+}
+
+fn parse_u64(value: &str) -> Result<u64> {
+    value
+        .parse()
+        .map_err(|_| Error::parse(format!("invalid integer '{}'", value)))
+}
+
+fn parse_list(value: &str) -> Result<Vec<f64>> {
+    value.split(',').map(parse_f64).collect()
+}
+
+fn parse_triple(value: &str) -> Result<[f64; 3]> {
+    match parse_list(value)?[..] {
+        [x, y, z] => Ok([x, y, z]),
+        _ => Err(Error::parse(format!(
+            "expected 3 comma separated values, found '{}'",
+            value
+        ))),
+    }
+}
+
+fn parse_point(value: &str) -> Result<Point> {
+    let [x, y, z] = parse_triple(value)?;
+    Ok(Point::new(x, y, z))
+}
+
+fn parse_vector(value: &str) -> Result<Vector> {
+    let [x, y, z] = parse_triple(value)?;
+    Ok(Vector::new(x, y, z))
+}
+
+fn parse_color(value: &str) -> Result<Color> {
+    let [r, g, b] = parse_triple(value)?;
+    Ok(Color::new(r, g, b))
+}
+
+#[cfg(test)]
+mod scene_tests {
+    use super::*;
+    use crate::features::consts::EPSILON;
+
+    const SCENE: &str = "
+        # two spheres on a floor
+        camera width=30 height=20 fov=45 from=0,1,-5 to=0,1,0 up=0,1,0 samples=2
+        light position=-10,10,-10 intensity=1,1,1
+        pattern stripes stripe a=1,0,0 b=0,0,1 scale=0.5,0.5,0.5
+        material red color=1,0,0 diffuse=0.7 pattern=stripes
+        plane
+        sphere material=red scale=0.5,0.5,0.5 translate=0,1,0
+    ";
+
+    #[test]
+    fn parse_scene() {
+        let scene = Scene::parse(SCENE).unwrap();
+        assert_eq!(scene.camera.hsize, 30.0);
+        assert_eq!(scene.camera.vsize, 20.0);
+        assert_eq!(scene.camera.samples, 2);
+        assert_eq!(scene.world.light.position, Point::new(-10.0, 10.0, -10.0));
+        assert_eq!(scene.world.keys.len(), 2);
+        let sphere = scene.world.objects.get(&scene.world.keys[1]).unwrap();
+        assert_eq!(
+            sphere.get_transform(),
+            translation(0.0, 1.0, 0.0) * scaling(0.5, 0.5, 0.5)
+        );
+        assert_eq!(sphere.get_material().color, Color::new(1.0, 0.0, 0.0));
+        assert!(sphere.get_material().pattern.is_some());
+    }
+
+    #[test]
+    fn errors_report_line_number() {
+        let err = Scene::parse("camera\nlight\nsphere material=missing").err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "line 3: unknown material 'missing'"
+        );
+    }
+
+    #[test]
+    fn perturbed_pattern() {
+        let scene = Scene::parse(
+            "camera\nlight\npattern s stripe\npattern marble perturb pattern=s amount=0.3\n\
+             material m pattern=marble\nsphere material=m",
+        )
+        .unwrap();
+        let sphere = scene.world.objects.get(&scene.world.keys[0]).unwrap();
+        assert!(matches!(
+            sphere.get_material().pattern,
+            Some(Pattern::Perturbed(_))
+        ));
+        let err = Scene::parse("pattern marble perturb").err();
+        assert_eq!(err.unwrap().to_string(), "line 1: perturb needs a pattern");
+    }
+
+    #[test]
+    fn normal_modifiers() {
+        let scene = Scene::parse(
+            "camera\nlight\nnormal waves bump amount=0.2 octaves=4 scale=0.5,0.5,0.5\n\
+             material water color=0,0.2,0.6 normal=waves\nplane material=water",
+        )
+        .unwrap();
+        let plane = scene.world.objects.get(&scene.world.keys[0]).unwrap();
+        assert!(matches!(
+            plane.get_material().normal,
+            Some(NormalModifier::Bump(_))
+        ));
+        let err = Scene::parse("normal n height-map amount=2").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: height-map needs an image");
+        let err = Scene::parse("material m normal=nope").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: unknown normal 'nope'");
+    }
+
+    #[test]
+    fn pbr_materials() {
+        let scene = Scene::parse(
+            "camera\nlight\npattern s stripe\n\
+             material gold model=pbr base-color=1,0.8,0.3 metallic=1 roughness=0.3\n\
+             material lamp color=0.5,0.5,0.5 roughness=s emissive=1,1,0.8\n\
+             material plain model=pbr model=phong\n\
+             sphere material=gold\nsphere material=lamp\nsphere material=plain",
+        )
+        .unwrap();
+        let model = |ix: usize| {
+            scene.world.objects[&scene.world.keys[ix]]
+                .get_material()
+                .model
+        };
+        match model(0) {
+            Model::CookTorrance(pbr) => {
+                assert_eq!(pbr.base_color, Pattern::from(Color::new(1.0, 0.8, 0.3)));
+                assert_eq!(pbr.metallic, Pattern::from(WHITE));
+            }
+            _ => panic!("expected a pbr material"),
+        }
+        match model(1) {
+            Model::CookTorrance(pbr) => {
+                assert_eq!(pbr.base_color, Pattern::from(Color::new(0.5, 0.5, 0.5)));
+                assert!(matches!(pbr.roughness, Pattern::Stripe(_)));
+                assert_eq!(pbr.emissive, Pattern::from(Color::new(1.0, 1.0, 0.8)));
+            }
+            _ => panic!("expected a pbr material"),
+        }
+        assert_eq!(model(2), Model::Phong);
+        let err = Scene::parse("material m model=disney").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: unknown material model 'disney'");
+    }
+
+    #[test]
+    fn emissive_materials() {
+        let scene = Scene::parse(
+            "camera\nworld light-samples=8\n\
+             material neon emission=1,0.2,0.6 emission-strength=4\n\
+             plane\nsphere material=neon translate=0,2,0",
+        )
+        .unwrap();
+        assert_eq!(scene.world.light.intensity, BLACK);
+        assert_eq!(scene.world.light_samples, 8);
+        assert_eq!(scene.world.get_emitters(), &scene.world.keys[1..]);
+        let neon = scene.world.objects[&scene.world.keys[1]].get_material();
+        assert_eq!(neon.emission, Color::new(1.0, 0.2, 0.6));
+        assert_eq!(neon.emission_strength, 4.0);
+        let err = Scene::parse("camera\nplane").err().unwrap();
+        assert_eq!(err.to_string(), "scene has no light");
+    }
+
+    #[test]
+    fn path_tracer_camera() {
+        let scene = Scene::parse("camera integrator=path max-depth=6 seed=42\nlight").unwrap();
+        assert_eq!(
+            scene.camera.integrator,
+            Integrator::PathTracer(PathTracer::new(6, 3))
+        );
+        assert_eq!(scene.camera.seed, 42);
+        let scene = Scene::parse("camera\nlight").unwrap();
+        assert_eq!(scene.camera.integrator, Integrator::Whitted);
+        let err = Scene::parse("camera integrator=photon").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: unknown integrator 'photon'");
+    }
+
+    #[test]
+    fn ambient_occlusion() {
+        let scene = Scene::parse(
+            "camera integrator=ao ao-samples=64 ao-distance=3\nlight\nworld ao-distance=0.5",
+        )
+        .unwrap();
+        assert_eq!(
+            scene.camera.integrator,
+            Integrator::AmbientOcclusion(AmbientOcclusion::new(64, 3.0))
+        );
+        assert_eq!(
+            scene.world.ambient_occlusion,
+            Some(AmbientOcclusion::new(16, 0.5))
+        );
+        let scene = Scene::parse("camera\nlight").unwrap();
+        assert_eq!(scene.world.ambient_occlusion, None);
+    }
+
+    #[test]
+    fn fog_volumes_and_spot_lights() {
+        let scene = Scene::parse(
+            "camera
+light position=0,10,0 to=0,0,0 angle=20 inner=10
+world fog-density=0.05
+material smoke color=0.8,0.8,0.8
+volume sphere density=0.5 material=smoke scale=2,2,2",
+        )
+        .unwrap();
+        let world = &scene.world;
+        assert_eq!(world.fog, Some(Fog::new(Color::new(0.5, 0.5, 0.5), 0.05)));
+        let spot = world.light.spot.unwrap();
+        assert_eq!(spot.direction, Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(spot.cos_outer, 20.0_f64.to_radians().cos());
+        assert_eq!(spot.cos_inner, 10.0_f64.to_radians().cos());
+        let volume = world.get_shape(&world.keys[0]).unwrap();
+        assert!(volume.is_volume());
+        assert_eq!(volume.get_transform(), scaling(2.0, 2.0, 2.0));
+        assert_eq!(volume.get_material().color, Color::new(0.8, 0.8, 0.8));
+
+        let err = Scene::parse("volume sphere density=0").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: volume density must be positive");
+        let err = Scene::parse("volume plane").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: unknown volume boundary 'plane'");
+    }
+
+    #[test]
+    fn backgrounds() {
+        let scene = Scene::parse("camera\nlight\nbackground sky zenith=0,0,1").unwrap();
+        assert_eq!(
+            scene.world.background,
+            Background::Sky {
+                horizon: WHITE,
+                zenith: Color::new(0.0, 0.0, 1.0)
+            }
+        );
+        let scene = Scene::parse("camera\nlight").unwrap();
+        assert_eq!(scene.world.background, Background::Solid(BLACK));
+
+        let dir = std::env::temp_dir().join(format!("rt-background-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut hdr = b"#?RADIANCE\n\n-Y 1 +X 1\n".to_vec();
+        hdr.extend([128, 128, 128, 130]);
+        fs::write(dir.join("sky.hdr"), hdr).unwrap();
+        fs::write(
+            dir.join("env.scene"),
+            "camera\nlight\nbackground environment image=sky.hdr rotate=90 strength=0.5",
+        )
+        .unwrap();
+        let scene = Scene::load(dir.join("env.scene")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let c = scene.world.background.at(&Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(c, Color::new(1.0, 1.0, 1.0));
+
+        let err = Scene::parse("background environment").err().unwrap();
+        assert_eq!(err.to_string(), "line 1: environment needs an image");
+    }
+
+    #[test]
+    fn checker_offsets() {
+        let scene = Scene::parse(
+            "camera\nlight\npattern a safe-checker\npattern b checker offset=0.01\n\
+             material ma pattern=a\nmaterial mb pattern=b\nplane material=ma\nplane material=mb",
+        )
+        .unwrap();
+        let offsets: Vec<f64> = scene
+            .world
+            .keys
+            .iter()
+            .map(|k| match scene.world.objects[k].get_material().pattern {
+                Some(Pattern::Checker(c)) => c.get_offset(),
+                _ => panic!("expected a checker pattern"),
+            })
+            .collect();
+        assert_eq!(offsets, vec![EPSILON, 0.01]);
+    }
+
+    #[test]
+    fn nested_patterns() {
+        let scene = Scene::parse(
+            "camera\nlight\npattern s stripe a=1,0,0 b=0,0,1 scale=0.5,1,1\n\
+             pattern mix blend a=s b=0.5,0.5,0.5\npattern c checker a=mix b=s\n\
+             material m pattern=c\nsphere material=m",
+        )
+        .unwrap();
+        let sphere = scene.world.objects.get(&scene.world.keys[0]).unwrap();
+        match sphere.get_material().pattern {
+            Some(Pattern::Checker(c)) => {
+                assert_eq!(
+                    c.at(&Point::new(0.25, 0.0, 0.0)),
+                    Color::new(0.75, 0.25, 0.25)
+                )
+            }
+            _ => panic!("expected a checker pattern"),
+        }
+        let err = Scene::parse("pattern c checker a=nope").err();
+        assert_eq!(err.unwrap().to_string(), "line 1: unknown pattern 'nope'");
+    }
+
+    #[test]
+    fn texture_patterns() {
+        let dir = std::env::temp_dir().join(format!("rt-scene-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("red.ppm"), "P3\n1 1\n255\n255 0 0\n").unwrap();
+        fs::write(
+            dir.join("textured.scene"),
+            "camera\nlight\n\
+             pattern globe texture mapping=spherical uv=checkers width=16 height=8\n\
+             pattern img texture mapping=planar uv=red.ppm\n\
+             pattern box cube-map front=red.ppm up=align-check\n\
+             material m pattern=img\nplane material=m",
+        )
+        .unwrap();
+        let scene = Scene::load(dir.join("textured.scene")).unwrap();
+        let plane = scene.world.objects.get(&scene.world.keys[0]).unwrap();
+        let pattern = plane.get_material().pattern.unwrap();
+        assert_eq!(
+            pattern.at(&Point::new(0.3, 0.0, 0.7)),
+            Color::new(1.0, 0.0, 0.0)
+        );
+        // an image used twice is one file the scene depends on
+        assert_eq!(scene.files, [dir.join("red.ppm")]);
+        let err = Scene::load(dir.join("missing.scene")).err().unwrap();
+        assert!(matches!(err, Error::Io(_)));
+        let source = fs::read_to_string(dir.join("textured.scene")).unwrap();
+        let files = [(dir.join("red.ppm"), fs::read(dir.join("red.ppm")).unwrap())];
+        fs::remove_dir_all(&dir).unwrap();
+        // files sent along with a scene stand in for the disk
+        let sent = Scene::parse_with_files(&source, dir.join("textured.scene"), &files).unwrap();
+        assert_eq!(sent.files, scene.files);
+        let err = Scene::parse_with_files(&source, dir.join("textured.scene"), &[])
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .ends_with("red.ppm: not among the files sent with the scene"));
+
+        let err = Scene::parse("pattern t texture uv=missing.ppm")
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("line 1: missing.ppm: "));
+    }
+
+    #[test]
+    fn animate_keys_shapes_lights_and_the_camera() {
+        let scene = Scene::parse(
+            "
+            camera from=0,0,-5 to=0,0,0
+            light position=0,10,0
+            sphere name=ball scale=2,2,2
+            animate ball translate 0:0,0,0 2:4,0,0
+            animate ball rotate spline 0:0,0,0 1:0,90,0 2:0,180,0
+            animate ball diffuse 0:0.1 1:0.5
+            animate light intensity 0:0,0,0 1:1,1,1
+            animate camera from 0:0,0,-5 1:5,0,0
+            ",
+        )
+        .unwrap();
+        let id = scene.world.keys[0].clone();
+        let tracks = &scene.animation.shapes[&id];
+        assert_eq!(tracks.base, scaling(2.0, 2.0, 2.0));
+        assert_eq!(tracks.translate.as_ref().unwrap().get_keys().len(), 2);
+        let rotate = tracks.rotate.as_ref().unwrap();
+        assert_eq!(rotate.interpolation, Interpolation::Spline);
+        assert_eq!(rotate.at(1.0), Some(Vector::new(0.0, PI / 2.0, 0.0)));
+        assert_eq!(tracks.diffuse.as_ref().unwrap().at(0.5), Some(0.3));
+        assert_eq!(
+            scene.animation.light.intensity.as_ref().unwrap().at(0.5),
+            Some(Color::new(0.5, 0.5, 0.5))
+        );
+        // the parts of the camera left alone keep the scene's values
+        let path = scene.animation.camera.as_ref().unwrap();
+        assert_eq!(path.from.at(1.0), Some(Point::new(5.0, 0.0, 0.0)));
+        assert_eq!(path.to.at(1.0), Some(Point::new(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn bad_animations_are_reported() {
+        let scene = "camera\nlight\nsphere name=ball\n";
+        for (line, message) in [
+            (
+                "animate ball",
+                "line 4: expected 'animate <target> <property> <time>:<value> ...'",
+            ),
+            (
+                "animate ball translate",
+                "line 4: expected at least one <time>:<value> key",
+            ),
+            (
+                "animate ball translate 0=1,1,1",
+                "line 4: expected <time>:<value>, found '0=1,1,1'",
+            ),
+            (
+                "animate box translate 0:1,1,1",
+                "line 4: unknown shape 'box'",
+            ),
+            (
+                "animate ball density 0:1",
+                "line 4: cannot animate the density of ball",
+            ),
+            ("sphere name=ball", "line 4: shape name 'ball' is taken"),
+        ] {
+            let err = Scene::parse(&format!("{}{}", scene, line)).err();
+            assert_eq!(err.unwrap().to_string(), message);
+        }
+    }
+
+    #[test]
+    fn missing_camera() {
+        let err = Scene::parse("light position=0,0,0").err();
+        assert_eq!(err.unwrap().to_string(), "scene has no camera");
+    }
+
+    #[test]
+    fn invalid_number() {
+        let err = Scene::parse("camera width=abc").err();
+        assert_eq!(err.unwrap().to_string(), "line 1: invalid integer 'abc'");
+    }
+
+    #[test]
+    fn singular_transform_is_reported_at_its_line() {
+        let err = Scene::parse("camera\nlight\n\nsphere scale=1,0,1").err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "line 4: transform is not invertible"
+        );
+    }
+
+    #[test]
+    fn invalid_material_is_reported_at_its_line() {
+        let err = Scene::parse("material bad shininess=-1").err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "line 1: invalid material: shininess must be positive, found -1"
+        );
+    }
+}
@@ -1,22 +1,77 @@
-use super::{material::Material, matrice::Matrice, point::Point, ray::Ray, vector::Vector};
+use std::borrow::Cow;
+
+use super::{
+    error::Result, material::Material, matrice::Matrice, motion::Motion, point::Point, ray::Ray,
+    vector::Vector,
+};
 
 pub mod plane;
 pub mod sphere;
+pub mod volume;
+
+// a point picked on a shape's surface, with the area it stands for (the inverse of its pdf)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceSample {
+    pub point: Point,
+    pub normal: Vector,
+    pub area: f64,
+}
+
 pub trait Shape: Sync {
     fn intersect(&self, r: &Ray) -> Option<Vec<f64>> {
-        self.local_intersect(&r.transform(&self.get_transform().inverse()?))
+        self.local_intersect(&r.transform(&self.inverse_at(r.time)))
     }
     fn local_intersect(&self, r: &Ray) -> Option<Vec<f64>>;
-    fn set_transform(&mut self, transform: Matrice);
-    fn set_material(&mut self, material: Material);
+    fn set_transform(&mut self, transform: Matrice) -> Result<()>;
+    fn set_material(&mut self, material: Material) -> Result<()>;
     fn get_material(&self) -> Material;
     fn get_transform(&self) -> Matrice;
-    fn normal_at(&self, world_point: Point) -> Option<Vector> {
-        let local_point = self.get_transform().inverse()? * world_point;
+    fn get_inverse(&self) -> &Matrice;
+    // the transform at the end of the shutter interval, for shapes that move while it
+    // is open; the shape's own transform is where it starts
+    fn set_motion(&mut self, end: Option<Matrice>) -> Result<()>;
+    fn get_motion(&self) -> Option<&Motion>;
+    // still shapes lend their inverse as it is, so they cost nothing extra
+    fn inverse_at(&self, time: f64) -> Cow<'_, Matrice> {
+        match self.get_motion() {
+            Some(motion) => Cow::Owned(motion.inverse_at(time)),
+            None => Cow::Borrowed(self.get_inverse()),
+        }
+    }
+    fn normal_at(&self, world_point: Point) -> Vector {
+        let local_point = self.get_inverse().clone() * world_point;
         let local_normal = self.local_normal_at(local_point);
-        let world_normal = self.get_transform().inverse()?.transpose() * local_normal;
-        Some(world_normal.normalize())
+        let world_normal = self.get_inverse().transpose() * local_normal;
+        world_normal.normalize()
     }
     fn local_normal_at(&self, local_point: Point) -> Vector;
+    // uniformly picks a point on the untransformed surface for u, v in [0, 1), with its
+    // normal and the surface's total area; infinite shapes can't be sampled
+    fn local_sample(&self, _u: f64, _v: f64) -> Option<(Point, Vector, f64)> {
+        None
+    }
+    fn sample(&self, u: f64, v: f64) -> Option<SurfaceSample> {
+        let (local_point, local_normal, local_area) = self.local_sample(u, v)?;
+        let transform = self.get_transform();
+        let normal = self.get_inverse().transpose() * local_normal;
+        // a transform stretches each patch of surface by its determinant times
+        // how much it stretches the patch's normal
+        let stretch = transform.submatrix(3, 3).determinant().abs() * normal.magnitude();
+        Some(SurfaceSample {
+            point: transform * local_point,
+            normal: normal.normalize(),
+            area: local_area * stretch,
+        })
+    }
+    // participating media scatter light evenly in every direction instead of off a surface
+    fn is_volume(&self) -> bool {
+        false
+    }
     fn get_shape_id(&self) -> String;
+    // the name of the shape's type, which its intersection tests are counted under
+    fn kind(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name)
+    }
 }
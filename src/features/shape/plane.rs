@@ -1,6 +1,7 @@
 use super::Shape;
 use crate::features::{
-    consts::EPSILON, material::Material, matrice::Matrice, point::Point, ray::Ray, vector::Vector,
+    consts::EPSILON, error::Result, material::Material, matrice::Matrice, motion::Motion,
+    point::Point, ray::Ray, vector::Vector,
 };
 use uuid::Uuid;
 
@@ -8,32 +9,46 @@ use uuid::Uuid;
 pub struct Plane {
     id: String,
     transform: Matrice,
+    inverse: Matrice,
     material: Material,
+    motion: Option<Motion>,
 }
 
 impl Plane {
-    pub fn new(transform: Matrice, material: Material) -> Self {
-        Plane {
-            transform,
-            material,
-            id: Uuid::new_v4().to_string(),
-        }
+    pub fn new(transform: Matrice, material: Material) -> Result<Self> {
+        let mut shape = Plane::default();
+        shape.set_transform(transform)?;
+        shape.set_material(material)?;
+        Ok(shape)
     }
 }
 
 impl Default for Plane {
     fn default() -> Self {
-        Plane::new(Matrice::identity_matrix(4), Material::default())
+        Plane {
+            id: Uuid::new_v4().to_string(),
+            transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
+            material: Material::default(),
+            motion: None,
+        }
     }
 }
 
 impl Shape for Plane {
-    fn set_transform(&mut self, transform: Matrice) {
+    fn set_transform(&mut self, transform: Matrice) -> Result<()> {
+        self.inverse = transform.inverse()?;
+        if let Some(motion) = &self.motion {
+            self.motion = Some(Motion::new(&transform, motion.get_end())?);
+        }
         self.transform = transform;
+        Ok(())
     }
 
-    fn set_material(&mut self, material: Material) {
+    fn set_material(&mut self, material: Material) -> Result<()> {
+        material.validate()?;
         self.material = material;
+        Ok(())
     }
 
     fn get_material(&self) -> Material {
@@ -44,6 +59,22 @@ impl Shape for Plane {
         self.transform.clone()
     }
 
+    fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+
+    fn set_motion(&mut self, end: Option<Matrice>) -> Result<()> {
+        self.motion = match end {
+            Some(end) => Some(Motion::new(&self.transform, &end)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn get_motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+
     fn local_normal_at(&self, _world_point: Point) -> Vector {
         Vector::new(0.0, 1.0, 0.0)
     }
@@ -1,36 +1,55 @@
+use std::f64::consts::PI;
+
 use super::Shape;
-use crate::features::{material::Material, matrice::Matrice, point::Point, vector::Vector};
+use crate::features::{
+    error::Result, material::Material, matrice::Matrice, motion::Motion, point::Point,
+    vector::Vector,
+};
 use uuid::Uuid;
 
 #[derive(Debug, PartialEq)]
 pub struct Sphere {
     id: String,
     transform: Matrice,
+    inverse: Matrice,
     material: Material,
+    motion: Option<Motion>,
 }
 
 impl Sphere {
-    pub fn new(transform: Matrice, material: Material) -> Self {
-        Sphere {
-            transform,
-            material,
-            id: Uuid::new_v4().to_string(),
-        }
+    pub fn new(transform: Matrice, material: Material) -> Result<Self> {
+        let mut shape = Sphere::default();
+        shape.set_transform(transform)?;
+        shape.set_material(material)?;
+        Ok(shape)
     }
 }
 
 impl Default for Sphere {
     fn default() -> Self {
-        Sphere::new(Matrice::identity_matrix(4), Material::default())
+        Sphere {
+            id: Uuid::new_v4().to_string(),
+            transform: Matrice::identity_matrix(4),
+            inverse: Matrice::identity_matrix(4),
+            material: Material::default(),
+            motion: None,
+        }
     }
 }
 
 impl Shape for Sphere {
-    fn set_transform(&mut self, transform: Matrice) {
+    fn set_transform(&mut self, transform: Matrice) -> Result<()> {
+        self.inverse = transform.inverse()?;
+        if let Some(motion) = &self.motion {
+            self.motion = Some(Motion::new(&transform, motion.get_end())?);
+        }
         self.transform = transform;
+        Ok(())
     }
-    fn set_material(&mut self, material: Material) {
+    fn set_material(&mut self, material: Material) -> Result<()> {
+        material.validate()?;
         self.material = material;
+        Ok(())
     }
     fn get_material(&self) -> Material {
         self.material.clone()
@@ -38,12 +57,32 @@ impl Shape for Sphere {
     fn get_transform(&self) -> Matrice {
         self.transform.clone()
     }
+    fn get_inverse(&self) -> &Matrice {
+        &self.inverse
+    }
+    fn set_motion(&mut self, end: Option<Matrice>) -> Result<()> {
+        self.motion = match end {
+            Some(end) => Some(Motion::new(&self.transform, &end)?),
+            None => None,
+        };
+        Ok(())
+    }
+    fn get_motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
     fn get_shape_id(&self) -> String {
         self.id.clone()
     }
     fn local_normal_at(&self, world_point: Point) -> Vector {
         world_point - Point::new(0.0, 0.0, 0.0)
     }
+    fn local_sample(&self, u: f64, v: f64) -> Option<(Point, Vector, f64)> {
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let normal = Vector::new(r * phi.cos(), r * phi.sin(), z);
+        Some((Point::new(0.0, 0.0, 0.0) + normal, normal, 4.0 * PI))
+    }
 
     fn local_intersect(&self, r: &crate::features::ray::Ray) -> Option<Vec<f64>> {
         let sphere_to_ray = r.origin - Point::default();
@@ -113,7 +152,7 @@ mod sphere_intersects_tests {
 
 #[cfg(test)]
 mod sphere_shape_trait_tests {
-    use crate::features::transformations::translation;
+    use crate::features::transformations::{scaling, translation};
 
     use super::*;
     #[test]
@@ -126,10 +165,28 @@ mod sphere_shape_trait_tests {
     fn change_sphere_transformation() {
         let mut s = Sphere::default();
         let t = translation(2.0, 3.0, 4.0);
-        s.set_transform(t.clone());
+        s.set_transform(t.clone()).unwrap();
         assert_eq!(s.transform, t);
     }
 
+    #[test]
+    fn singular_transformation_is_rejected() {
+        let mut s = Sphere::default();
+        assert!(s.set_transform(scaling(1.0, 0.0, 1.0)).is_err());
+        assert_eq!(s.transform, Matrice::identity_matrix(4));
+    }
+
+    #[test]
+    fn invalid_material_is_rejected() {
+        let mut s = Sphere::default();
+        let m = Material {
+            ambient: -1.0,
+            ..Default::default()
+        };
+        assert!(s.set_material(m).is_err());
+        assert_eq!(s.material, Material::default());
+    }
+
     #[test]
     fn default_material() {
         let s = Sphere::default();
@@ -158,34 +215,32 @@ mod normals_tests {
     #[test]
     fn on_x_axis() {
         let s = Sphere::default();
-        let n = s.normal_at(Point::new(1.0, 0.0, 0.0)).unwrap();
+        let n = s.normal_at(Point::new(1.0, 0.0, 0.0));
         assert_eq!(n, Vector::new(1.0, 0.0, 0.0))
     }
 
     #[test]
     fn on_y_axis() {
         let s = Sphere::default();
-        let n = s.normal_at(Point::new(0.0, 1.0, 0.0)).unwrap();
+        let n = s.normal_at(Point::new(0.0, 1.0, 0.0));
         assert_eq!(n, Vector::new(0.0, 1.0, 0.0))
     }
 
     #[test]
     fn z_axis() {
         let s = Sphere::default();
-        let n = s.normal_at(Point::new(0.0, 0.0, 1.0)).unwrap();
+        let n = s.normal_at(Point::new(0.0, 0.0, 1.0));
         assert_eq!(n, Vector::new(0.0, 0.0, 1.0))
     }
 
     #[test]
     fn on_nonaxial() {
         let s = Sphere::default();
-        let n = s
-            .normal_at(Point::new(
-                3.0_f64.sqrt() / 3.0,
-                3.0_f64.sqrt() / 3.0,
-                3.0_f64.sqrt() / 3.0,
-            ))
-            .unwrap();
+        let n = s.normal_at(Point::new(
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
+        ));
         assert_eq!(
             n,
             Vector::new(
@@ -198,23 +253,19 @@ mod normals_tests {
     #[test]
     fn is_normalized() {
         let s = Sphere::default();
-        let n = s
-            .normal_at(Point::new(
-                3.0_f64.sqrt() / 3.0,
-                3.0_f64.sqrt() / 3.0,
-                3.0_f64.sqrt() / 3.0,
-            ))
-            .unwrap();
+        let n = s.normal_at(Point::new(
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
+        ));
         assert_eq!(n, n.normalize());
     }
 
     #[test]
     fn translated_sphere() {
         let mut s = Sphere::default();
-        s.set_transform(translation(0.0, 1.0, 0.0));
-        let n = s
-            .normal_at(Point::new(0.0, 1.70711, -FRAC_1_SQRT_2))
-            .unwrap();
+        s.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        let n = s.normal_at(Point::new(0.0, 1.70711, -FRAC_1_SQRT_2));
         assert_eq!(n, Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
     }
 
@@ -222,14 +273,42 @@ mod normals_tests {
     fn transformed_sphere() {
         let mut s = Sphere::default();
         let m = scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0);
-        s.set_transform(m);
-        let n = s
-            .normal_at(Point::new(
-                0.0,
-                2.0_f64.sqrt() / 2.0,
-                -(2.0_f64.sqrt() / 2.0),
-            ))
-            .unwrap();
+        s.set_transform(m).unwrap();
+        let n = s.normal_at(Point::new(
+            0.0,
+            2.0_f64.sqrt() / 2.0,
+            -(2.0_f64.sqrt() / 2.0),
+        ));
         assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
     }
 }
+
+#[cfg(test)]
+mod sphere_sample_tests {
+    use super::*;
+    use crate::features::transformations::{scaling, translation};
+
+    #[test]
+    fn samples_lie_on_the_surface() {
+        let mut s = Sphere::default();
+        s.set_transform(translation(1.0, 2.0, 3.0) * scaling(2.0, 2.0, 2.0))
+            .unwrap();
+        for (u, v) in [(0.0, 0.0), (0.3, 0.7), (0.5, 0.25), (0.99, 0.5)] {
+            let sample = s.sample(u, v).unwrap();
+            let offset = sample.point - Point::new(1.0, 2.0, 3.0);
+            assert!((offset.magnitude() - 2.0).abs() < 1e-9);
+            assert!((offset.normalize() - sample.normal).magnitude() < 1e-9);
+            assert!((sample.area - 16.0 * PI).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn stretched_area_follows_the_normal() {
+        // on the flat faces of a disc-like spheroid the area barely stretches
+        let mut s = Sphere::default();
+        s.set_transform(scaling(3.0, 3.0, 0.5)).unwrap();
+        let pole = s.sample(0.0, 0.0).unwrap();
+        assert_eq!(pole.normal, Vector::new(0.0, 0.0, 1.0));
+        assert!((pole.area - 4.0 * PI * 9.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,163 @@
+use super::Shape;
+use crate::features::{
+    error::Result, integrator::mix, material::Material, matrice::Matrice, motion::Motion,
+    point::Point, ray::Ray, vector::Vector,
+};
+use uuid::Uuid;
+
+// a cloud of evenly spread particles filling a closed boundary shape; rays scatter off it
+// at a random depth inside rather than at its surface, so it renders as smoke or haze
+pub struct Volume {
+    id: String,
+    boundary: Box<dyn Shape>,
+    // the chance of scattering per unit of distance travelled inside
+    density: f64,
+    material: Material,
+}
+
+impl Volume {
+    pub fn new(boundary: Box<dyn Shape>, density: f64, material: Material) -> Result<Self> {
+        let mut shape = Volume {
+            id: Uuid::new_v4().to_string(),
+            boundary,
+            density,
+            material: Material::default(),
+        };
+        shape.set_material(material)?;
+        Ok(shape)
+    }
+    pub fn get_density(&self) -> f64 {
+        self.density
+    }
+    pub fn set_density(&mut self, density: f64) {
+        self.density = density;
+    }
+}
+
+impl Shape for Volume {
+    // the scattering depth is drawn from a hash of the ray so a ray always meets the
+    // volume at the same place, whichever thread or random stream traces it
+    fn intersect(&self, r: &Ray) -> Option<Vec<f64>> {
+        let mut ts = self.boundary.intersect(r)?;
+        ts.sort_by(f64::total_cmp);
+        let entry = ts.first()?.max(0.0);
+        let exit = *ts.last()?;
+        if exit <= entry || self.density <= 0.0 {
+            return None;
+        }
+        let speed = r.direction.magnitude();
+        let depth = -(1.0 - unit(r)).ln() / self.density;
+        if depth > (exit - entry) * speed {
+            return None;
+        }
+        Some(vec![entry + depth / speed])
+    }
+    fn local_intersect(&self, r: &Ray) -> Option<Vec<f64>> {
+        self.intersect(&r.transform(&self.get_transform()))
+    }
+    fn set_transform(&mut self, transform: Matrice) -> Result<()> {
+        self.boundary.set_transform(transform)
+    }
+    fn set_material(&mut self, material: Material) -> Result<()> {
+        material.validate()?;
+        self.material = material;
+        Ok(())
+    }
+    fn get_material(&self) -> Material {
+        self.material.clone()
+    }
+    fn get_transform(&self) -> Matrice {
+        self.boundary.get_transform()
+    }
+    fn get_inverse(&self) -> &Matrice {
+        self.boundary.get_inverse()
+    }
+    fn set_motion(&mut self, end: Option<Matrice>) -> Result<()> {
+        self.boundary.set_motion(end)
+    }
+    fn get_motion(&self) -> Option<&Motion> {
+        self.boundary.get_motion()
+    }
+    // particles have no surface to face, shading treats them as facing the eye
+    fn local_normal_at(&self, _local_point: Point) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+    }
+    fn is_volume(&self) -> bool {
+        true
+    }
+    fn get_shape_id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+// a number in [0, 1) that depends only on the ray
+fn unit(r: &Ray) -> f64 {
+    let (o, d) = (r.origin.position, r.direction.position);
+    let hash = [o.x, o.y, o.z, d.x, d.y, d.z]
+        .iter()
+        .fold(0, |acc, v| mix(acc ^ v.to_bits()));
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod volume_tests {
+    use super::*;
+    use crate::features::{shape::sphere::Sphere, transformations::translation};
+
+    fn fog(density: f64) -> Volume {
+        Volume::new(Box::<Sphere>::default(), density, Material::default()).unwrap()
+    }
+
+    fn rays() -> impl Iterator<Item = Ray> {
+        (0..1000).map(|i| {
+            let x = (i as f64 / 1000.0 - 0.5) * 0.2;
+            Ray::new(Point::new(x, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0))
+        })
+    }
+
+    #[test]
+    fn rays_scatter_inside_the_boundary() {
+        let volume = fog(1.0);
+        let mut hits = 0;
+        for r in rays() {
+            if let Some(ts) = volume.intersect(&r) {
+                assert_eq!(ts.len(), 1);
+                assert!(ts[0] >= 4.0 && ts[0] <= 6.0, "{}", ts[0]);
+                hits += 1;
+            }
+        }
+        // a chord of length two lets through e^-2 of the rays
+        let expected = 1000.0 * (1.0 - (-2.0_f64).exp());
+        assert!((hits as f64 - expected).abs() < 60.0, "{}", hits);
+    }
+
+    #[test]
+    fn dense_volumes_act_like_surfaces_and_thin_ones_vanish() {
+        let dense = fog(1e6);
+        assert!(rays().all(|r| {
+            let entry = 5.0 - (1.0 - r.origin.position.x.powi(2)).sqrt();
+            (dense.intersect(&r).unwrap()[0] - entry).abs() < 1e-3
+        }));
+        let thin = fog(1e-6);
+        assert!(rays().filter(|r| thin.intersect(r).is_some()).count() < 5);
+    }
+
+    #[test]
+    fn the_same_ray_scatters_at_the_same_depth() {
+        let volume = fog(1.0);
+        for r in rays().take(20) {
+            assert_eq!(volume.intersect(&r), volume.intersect(&r));
+        }
+    }
+
+    #[test]
+    fn volumes_follow_their_boundary() {
+        let mut volume = fog(1e6);
+        volume.set_transform(translation(0.0, 0.0, 2.0)).unwrap();
+        assert_eq!(volume.get_transform(), translation(0.0, 0.0, 2.0));
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!((volume.intersect(&r).unwrap()[0] - 6.0).abs() < 1e-3);
+        let inside = Ray::new(Point::new(0.0, 0.0, 2.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(volume.intersect(&inside).unwrap()[0] < 1e-3);
+    }
+}
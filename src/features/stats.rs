@@ -0,0 +1,132 @@
+use std::{borrow::Cow, cell::RefCell, collections::BTreeMap, ops::AddAssign, time::Duration};
+
+// work done while rendering. Every thread counts into its own copy, which renders
+// gather tile by tile, so counting costs no more than an add. There is no bounding
+// volume hierarchy yet, so every ray is tested against every shape and there are no
+// node visits to count
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Counters {
+    pub primary_rays: u64,
+    // shadow and ambient occlusion rays
+    pub shadow_rays: u64,
+    // path tracer bounces
+    pub secondary_rays: u64,
+    // intersection tests by the kind of shape tested, so new shapes count themselves
+    pub tests: BTreeMap<Cow<'static, str>, u64>,
+    pub inversions: u64,
+}
+
+impl Counters {
+    const ZERO: Counters = Counters {
+        primary_rays: 0,
+        shadow_rays: 0,
+        secondary_rays: 0,
+        tests: BTreeMap::new(),
+        inversions: 0,
+    };
+
+    pub fn intersection_tests(&self) -> u64 {
+        self.tests.values().sum()
+    }
+
+    pub fn tests_of(&self, kind: &str) -> u64 {
+        self.tests.get(kind).copied().unwrap_or_default()
+    }
+
+    // kinds are only allocated for shapes sent from elsewhere, so counting stays cheap
+    pub(crate) fn count_test(&mut self, kind: &'static str) {
+        match self.tests.get_mut(kind) {
+            Some(tests) => *tests += 1,
+            None => {
+                self.tests.insert(Cow::Borrowed(kind), 1);
+            }
+        }
+    }
+}
+
+impl AddAssign for Counters {
+    fn add_assign(&mut self, other: Self) {
+        self.primary_rays += other.primary_rays;
+        self.shadow_rays += other.shadow_rays;
+        self.secondary_rays += other.secondary_rays;
+        for (kind, tests) in other.tests {
+            *self.tests.entry(kind).or_default() += tests;
+        }
+        self.inversions += other.inversions;
+    }
+}
+
+// what a render did and how long each part of it took
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub counters: Counters,
+    // wall clock time of each phase, in the order they ran
+    pub phases: Vec<(&'static str, Duration)>,
+}
+
+thread_local! {
+    static COUNTERS: RefCell<Counters> = const { RefCell::new(Counters::ZERO) };
+}
+
+pub(crate) fn count(update: impl FnOnce(&mut Counters)) {
+    COUNTERS.with(|counters| update(&mut counters.borrow_mut()));
+}
+
+// what this thread counted since it was last taken
+pub(crate) fn take() -> Counters {
+    COUNTERS.with(|counters| counters.replace(Counters::ZERO))
+}
+
+// runs f and returns what it counted on this thread next to its result. The counts
+// are left in place, so a render taking them later still sees them
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Counters) {
+    let before = take();
+    let result = f();
+    let counted = take();
+    let kept = counted.clone();
+    count(|c| {
+        *c = before;
+        *c += kept;
+    });
+    (result, counted)
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn threads_count_apart() {
+        take();
+        count(|c| c.primary_rays += 2);
+        count(|c| c.count_test("Sphere"));
+        count(|c| c.count_test("Sphere"));
+        count(|c| c.count_test("Plane"));
+        let other = thread::spawn(|| {
+            count(|c| c.primary_rays += 5);
+            take()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(other.primary_rays, 5);
+        let mine = take();
+        assert_eq!(mine.primary_rays, 2);
+        assert_eq!(mine.intersection_tests(), 3);
+        assert_eq!(mine.tests_of("Sphere"), 2);
+        assert_eq!(mine.tests_of("Cube"), 0);
+        assert_eq!(take(), Counters::default());
+    }
+
+    #[test]
+    fn measuring_leaves_the_counts_in_place() {
+        take();
+        count(|c| c.primary_rays += 1);
+        let ((), counted) = measure(|| count(|c| c.shadow_rays += 3));
+        assert_eq!(counted.shadow_rays, 3);
+        assert_eq!(counted.primary_rays, 0);
+        let all = take();
+        assert_eq!((all.primary_rays, all.shadow_rays), (1, 3));
+    }
+}
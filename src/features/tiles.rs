@@ -0,0 +1,166 @@
+use std::f64::consts::PI;
+
+use super::{canvas::Canvas, color::Color};
+
+// a rectangle of pixels rendered as one unit of work
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// the order tiles are handed to the render threads
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TileOrder {
+    // outwards from the centre, where the subject usually is
+    #[default]
+    Spiral,
+    // along a space filling curve, keeping neighbouring tiles close in time
+    Hilbert,
+    // left to right, top to bottom
+    Scanline,
+}
+
+// what a render reports while it runs
+#[derive(Debug)]
+pub enum RenderEvent<'a> {
+    // a finished tile in full image coordinates, its pixels row by row as they stand
+    // after this pass
+    Tile {
+        pass: usize,
+        tile: &'a Tile,
+        pixels: &'a [Color],
+    },
+    // the whole image after a progressive pass
+    Pass {
+        pass: usize,
+        passes: usize,
+        image: &'a Canvas,
+    },
+}
+
+impl Tile {
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+// splits an image into tiles of at most size by size pixels, in the given order
+pub fn tiles(width: usize, height: usize, size: usize, order: TileOrder) -> Vec<Tile> {
+    let size = size.max(1);
+    let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+    let mut grid: Vec<(usize, usize)> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect();
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let centre = ((columns as f64 - 1.0) / 2.0, (rows as f64 - 1.0) / 2.0);
+            let key = |&(column, row): &(usize, usize)| {
+                let (dx, dy) = (column as f64 - centre.0, row as f64 - centre.1);
+                // rings of tiles around the centre, each swept clockwise from the top
+                let ring = dx.abs().max(dy.abs()).round();
+                let angle = dx.atan2(-dy).rem_euclid(2.0 * PI);
+                (ring, angle)
+            };
+            grid.sort_by(|a, b| {
+                let (a, b) = (key(a), key(b));
+                a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+            });
+        }
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            grid.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+        }
+    }
+    grid.into_iter()
+        .map(|(column, row)| Tile {
+            x: column * size,
+            y: row * size,
+            width: size.min(width - column * size),
+            height: size.min(height - row * size),
+        })
+        .collect()
+}
+
+// distance along the Hilbert curve filling a side by side grid
+fn hilbert_index(side: usize, x: usize, y: usize) -> usize {
+    let (mut x, mut y) = (x, y);
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = usize::from(x & s > 0);
+        let ry = usize::from(y & s > 0);
+        index += s * s * ((3 * rx) ^ ry);
+        // rotate the quadrant so the curve inside it joins up with its neighbours
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tile_tests {
+    use super::*;
+
+    fn coverage(width: usize, height: usize, size: usize, order: TileOrder) -> Vec<u32> {
+        let mut seen = vec![0; width * height];
+        for tile in tiles(width, height, size, order) {
+            for (x, y) in tile.pixels() {
+                seen[y * width + x] += 1;
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn every_order_covers_each_pixel_once() {
+        for order in [TileOrder::Spiral, TileOrder::Hilbert, TileOrder::Scanline] {
+            assert!(coverage(37, 23, 8, order).iter().all(|n| *n == 1));
+            assert!(coverage(5, 3, 16, order).iter().all(|n| *n == 1));
+        }
+    }
+
+    #[test]
+    fn edge_tiles_are_clipped() {
+        let t = tiles(10, 5, 4, TileOrder::Scanline);
+        assert_eq!(t.len(), 6);
+        assert_eq!(
+            t[5],
+            Tile {
+                x: 8,
+                y: 4,
+                width: 2,
+                height: 1
+            }
+        );
+    }
+
+    #[test]
+    fn spirals_start_in_the_middle() {
+        let t = tiles(50, 50, 10, TileOrder::Spiral);
+        assert_eq!((t[0].x, t[0].y), (20, 20));
+        // the first ring surrounds the centre before anything further out
+        assert!(t[1..9]
+            .iter()
+            .all(|t| t.x.abs_diff(20) <= 10 && t.y.abs_diff(20) <= 10));
+    }
+
+    #[test]
+    fn hilbert_steps_between_neighbours() {
+        let t = tiles(64, 64, 8, TileOrder::Hilbert);
+        for pair in t.windows(2) {
+            let step = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+            assert_eq!(step, 8);
+        }
+    }
+}
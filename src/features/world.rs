@@ -1,21 +1,36 @@
 use super::{
+    background::Background,
     color::Color,
     computation::Computation,
-    consts::BLACK,
+    consts::{BLACK, EPSILON},
+    error::{Error, Result},
+    fog::Fog,
     intersection::{hit, sort_intersections, Intersection},
     light::Light,
     material::Material,
+    matrice::Matrice,
+    motion::Posed,
+    occlusion::AmbientOcclusion,
     point::Point,
     ray::Ray,
     shape::{sphere::Sphere, Shape},
+    stats,
     transformations::scaling,
 };
-use std::collections::HashMap;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::HashMap, f64::consts::PI};
 
 pub struct World {
     pub light: Light,
     pub objects: HashMap<String, Box<dyn Shape>>,
     pub keys: Vec<String>,
+    // shadow rays traced towards each emissive shape per shading point
+    pub light_samples: usize,
+    // when set the ambient term is darkened where the surroundings hide the sky
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    pub background: Background,
+    pub fog: Option<Fog>,
+    emitters: Vec<String>,
 }
 
 impl World {
@@ -24,6 +39,11 @@ impl World {
             light,
             objects: HashMap::new(),
             keys: vec![],
+            light_samples: 1,
+            ambient_occlusion: None,
+            background: Background::default(),
+            fog: None,
+            emitters: vec![],
         }
     }
     pub fn add_shapes(&mut self, shapes: Vec<Box<dyn Shape>>) {
@@ -33,13 +53,18 @@ impl World {
     }
     pub fn add_shape(&mut self, shape: Box<dyn Shape>) {
         let id = shape.get_shape_id();
+        if shape.get_material().is_emissive() && shape.sample(0.5, 0.5).is_some() {
+            self.emitters.push(id.clone());
+        }
         self.objects.insert(id.clone(), shape);
         self.keys.push(id);
     }
     pub fn intersect(&self, ray: &Ray) -> Option<Vec<Intersection>> {
         let mut result = vec![];
         if !self.objects.is_empty() {
-            for shape in self.objects.values() {
+            // shapes are tried in the order they were added, never the map's, so that
+            // the sort, which keeps ties in order, breaks them the same way every run
+            for shape in self.keys.iter().filter_map(|key| self.objects.get(key)) {
                 if let Some(ixs) = Intersection::intersects(shape.as_ref(), ray) {
                     let mut ixs = ixs;
                     result.append(&mut ixs);
@@ -52,36 +77,139 @@ impl World {
         }
         None
     }
-    pub fn shade_hit(&self, comps: &Computation) -> Color {
-        let shape = self.objects.get(&comps.object_id).unwrap();
-        shape.get_material().lighting(
-            &self.light,
-            shape.as_ref(),
-            &comps.point,
-            &comps.eyev,
-            &comps.normalv,
-            self.is_shadowed(&comps.over_point),
-        )
+    pub fn get_shape(&self, id: &str) -> Result<&dyn Shape> {
+        self.objects
+            .get(id)
+            .map(|shape| shape.as_ref())
+            .ok_or_else(|| Error::UnknownShape(id.to_string()))
+    }
+    // the shape where it stands at a time in the shutter interval
+    pub fn get_shape_at(&self, id: &str, time: f64) -> Result<Posed<'_>> {
+        Ok(Posed::new(self.get_shape(id)?, time))
+    }
+    pub fn is_moving(&self) -> bool {
+        self.objects
+            .values()
+            .any(|shape| shape.get_motion().is_some())
+    }
+    // without a stream of their own, lookups draw from a fixed one so they repeat too
+    pub fn shade_hit(&self, comps: &Computation) -> Result<Color> {
+        self.shade_hit_with(comps, &mut StdRng::seed_from_u64(0))
+    }
+    pub fn shade_hit_with<R: Rng>(&self, comps: &Computation, rng: &mut R) -> Result<Color> {
+        let shape = &self.get_shape_at(&comps.object_id, comps.time)?;
+        let material = shape.get_material();
+        let mut ambient = material.ambient(&self.light, shape, &comps.point);
+        if let Some(ao) = &self.ambient_occlusion {
+            ambient = ambient * ao.estimate(self, comps, rng);
+        }
+        let unlit = material.emitted(shape, &comps.point) + ambient;
+        Ok(unlit + self.direct_light(shape, &material, comps, rng)?)
+    }
+
+    // light arriving straight from the main light and from emissive shapes, with shadows
+    pub fn direct_light<R: Rng>(
+        &self,
+        shape: &dyn Shape,
+        material: &Material,
+        comps: &Computation,
+        rng: &mut R,
+    ) -> Result<Color> {
+        // particles in a volume scatter a quarter of the light a white matte surface facing
+        // the light would, being lit from all sides and scattering to all sides
+        let reflected = |light: &Light| {
+            if shape.is_volume() {
+                return material.albedo(shape, &comps.point)
+                    * light.intensity_at(&comps.point)
+                    * 0.25;
+            }
+            material.direct(light, shape, &comps.point, &comps.eyev, &comps.normalv)
+        };
+        let mut color = BLACK;
+        // a dark main light is left in scenes lit only by emissive shapes
+        if self.light.intensity != BLACK && !self.is_shadowed(&comps.over_point, comps.time) {
+            color = reflected(&self.light);
+        }
+        if self.emitters.is_empty() {
+            return Ok(color);
+        }
+        for light in self.emitter_lights(&comps.over_point, comps.time, rng)? {
+            if !self.is_blocked(&comps.over_point, &light.position, EPSILON, comps.time) {
+                color = color + reflected(&light);
+            }
+        }
+        Ok(color)
+    }
+
+    pub fn get_emitters(&self) -> &[String] {
+        &self.emitters
     }
 
-    pub fn color_at(&self, ray: &Ray) -> Color {
-        if let Some(ixs) = self.intersect(ray) {
-            if let Some(hit) = hit(ixs) {
-                let comps =
-                    Computation::new(ray, &hit, self.objects.get(&hit.shape_id).unwrap().as_ref());
-                return self.shade_hit(&comps);
+    // samples points on every emissive shape, each standing in as a point light; like any
+    // light its intensity is what a white matte surface facing it reflects, which is the
+    // irradiance the patch of surface casts on the point divided by pi
+    pub fn emitter_lights<R: Rng>(
+        &self,
+        point: &Point,
+        time: f64,
+        rng: &mut R,
+    ) -> Result<Vec<Light>> {
+        let mut lights = vec![];
+        let samples = self.light_samples.max(1);
+        for id in &self.emitters {
+            let shape = &self.get_shape_at(id, time)?;
+            let material = shape.get_material();
+            for _ in 0..samples {
+                let sample = match shape.sample(rng.gen(), rng.gen()) {
+                    Some(sample) => sample,
+                    None => continue,
+                };
+                let to_point = *point - sample.point;
+                let distance = to_point.magnitude();
+                let cosine = sample.normal.dot_product(&to_point) / distance;
+                if distance < EPSILON || cosine <= 0.0 {
+                    continue;
+                }
+                let radiance = material.emitted(shape, &sample.point);
+                let weight = cosine * sample.area / (PI * distance * distance * samples as f64);
+                lights.push(Light::new(sample.point, radiance * weight));
             }
         }
-        BLACK
+        Ok(lights)
     }
-    pub fn is_shadowed(&self, point: &Point) -> bool {
-        let v = self.light.position - *point;
+
+    pub fn color_at(&self, ray: &Ray) -> Result<Color> {
+        self.color_at_with(ray, &mut StdRng::seed_from_u64(0))
+    }
+    pub fn color_at_with<R: Rng>(&self, ray: &Ray, rng: &mut R) -> Result<Color> {
+        if let Some(hit) = self.intersect(ray).and_then(hit) {
+            let comps = Computation::new(ray, &hit, &self.get_shape_at(&hit.shape_id, ray.time)?);
+            let color = self.shade_hit_with(&comps, rng)?;
+            return Ok(self.fogged(color, hit.t * ray.direction.magnitude()));
+        }
+        Ok(self.fogged(self.background.at(&ray.direction), f64::INFINITY))
+    }
+    pub fn fogged(&self, color: Color, distance: f64) -> Color {
+        match &self.fog {
+            Some(fog) => fog.apply(color, distance),
+            None => color,
+        }
+    }
+    pub fn is_shadowed(&self, point: &Point, time: f64) -> bool {
+        self.is_blocked(point, &self.light.position, 0.0, time)
+    }
+    // whether anything lies between the point and the target, short of a margin
+    // that lets a target on a surface ignore that surface, with shapes where they stand
+    // at a time in the shutter interval
+    pub fn is_blocked(&self, point: &Point, target: &Point, margin: f64, time: f64) -> bool {
+        let v = *target - *point;
         let distance = v.magnitude();
         let direction = v.normalize();
-        let r = Ray::new(*point, direction);
+        let r = Ray::new(*point, direction).with_time(time);
+        stats::count(|c| c.shadow_rays += 1);
         if let Some(ixs) = self.intersect(&r) {
             if let Some(h) = hit(ixs) {
-                if h.t < distance {
+                if h.t < distance - margin {
                     return true;
                 }
             }
@@ -96,17 +224,19 @@ impl Default for World {
             Point::new(-10.0, 10.0, -10.0),
             Color::new(1.0, 1.0, 1.0),
         ));
-        let mut s1: Box<dyn Shape> = Box::<Sphere>::default();
-        s1.set_material(Material {
-            color: Color::new(0.8, 1.0, 0.6),
-            diffuse: 0.7,
-            specular: 0.2,
-            ..Default::default()
-        });
-        let mut s2: Box<dyn Shape> = Box::<Sphere>::default();
-        s2.set_transform(scaling(0.5, 0.5, 0.5));
-        w.add_shape(s1);
-        w.add_shape(s2);
+        let s1 = Sphere::new(
+            Matrice::identity_matrix(4),
+            Material {
+                color: Color::new(0.8, 1.0, 0.6),
+                diffuse: 0.7,
+                specular: 0.2,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let s2 = Sphere::new(scaling(0.5, 0.5, 0.5), Material::default()).unwrap();
+        w.add_shape(Box::new(s1));
+        w.add_shape(Box::new(s2));
         w
     }
 }
@@ -128,13 +258,28 @@ mod world_tests {
         assert_eq!(xs[3].t, 6.0);
     }
 
+    #[test]
+    fn ties_go_to_the_shape_added_first() {
+        use crate::features::shape::plane::Plane;
+        let r = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        // every world hashes its shapes differently, so a few of them would tell
+        for _ in 0..16 {
+            let mut w = World::new(Light::new(Point::new(0.0, 10.0, 0.0), BLACK));
+            let (first, second) = (Plane::default(), Plane::default());
+            let id = first.get_shape_id();
+            w.add_shapes(vec![Box::new(first), Box::new(second)]);
+            let hit = hit(w.intersect(&r).unwrap()).unwrap();
+            assert_eq!(hit.shape_id, id);
+        }
+    }
+
     #[test]
     fn shade_intersection() {
         let w = World::default();
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let i = Intersection::new(&w.keys[0], 4.0);
         let comps = Computation::new(&r, &i, w.objects.get(&w.keys[0]).unwrap().as_ref());
-        let c = w.shade_hit(&comps);
+        let c = w.shade_hit(&comps).unwrap();
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
 
@@ -147,21 +292,21 @@ mod world_tests {
         let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
         let i = Intersection::new(&w.keys[1], 0.5);
         let comps = Computation::new(&r, &i, w.objects.get(&w.keys[1]).unwrap().as_ref());
-        let c = w.shade_hit(&comps);
+        let c = w.shade_hit(&comps).unwrap();
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
     }
     #[test]
     fn color_when_ray_misses() {
         let w = World::default();
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r).unwrap();
         assert_eq!(c, BLACK);
     }
     #[test]
     fn color_when_ray_hits() {
         let w = World::default();
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r).unwrap();
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
     #[test]
@@ -171,40 +316,40 @@ mod world_tests {
         let mut m = outer.get_material();
         m.ambient = 1.0;
 
-        outer.set_material(m);
+        outer.set_material(m).unwrap();
         let inner = w.objects.get_mut(&w.keys[1].clone()).unwrap();
         let mut m = inner.get_material();
         m.ambient = 1.0;
         let first_color = m.color;
-        inner.set_material(m);
+        inner.set_material(m).unwrap();
 
         let r = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r).unwrap();
         assert_eq!(c, first_color);
     }
     #[test]
     fn no_shadow() {
         let w = World::default();
         let p = Point::new(0.0, 10.0, 0.0);
-        assert!(!w.is_shadowed(&p));
+        assert!(!w.is_shadowed(&p, 0.0));
     }
     #[test]
     fn shadow_when_object_between_point_and_light() {
         let w = World::default();
         let p = Point::new(10.0, -10.0, 10.0);
-        assert!(w.is_shadowed(&p));
+        assert!(w.is_shadowed(&p, 0.0));
     }
     #[test]
     fn no_shadow_when_object_behind_light() {
         let w = World::default();
         let p = Point::new(-20.0, 20.0, -20.0);
-        assert!(!w.is_shadowed(&p));
+        assert!(!w.is_shadowed(&p, 0.0));
     }
     #[test]
     fn no_shadow_when_object_behind_point() {
         let w = World::default();
         let p = Point::new(-2.0, 2.0, -2.0);
-        assert!(!w.is_shadowed(&p));
+        assert!(!w.is_shadowed(&p, 0.0));
     }
     #[test]
     fn shade_hit_given_intersection_in_shadow() {
@@ -214,12 +359,155 @@ mod world_tests {
         ));
         w.add_shape(Box::<Sphere>::default());
         let mut s = Box::<Sphere>::default();
-        s.set_transform(translation(0.0, 0.0, 10.0));
+        s.set_transform(translation(0.0, 0.0, 10.0)).unwrap();
         w.add_shape(s);
         let ray = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
         let i = Intersection::new(&w.keys[1], 4.0);
         let comps = Computation::new(&ray, &i, w.objects.get(&w.keys[1]).unwrap().as_ref());
-        let c = w.shade_hit(&comps);
+        let c = w.shade_hit(&comps).unwrap();
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
+
+    fn glowing_sphere(height: f64) -> Box<Sphere> {
+        let material = Material {
+            emission: Color::new(1.0, 1.0, 1.0),
+            ..Default::default()
+        };
+        Box::new(Sphere::new(translation(0.0, height, 0.0), material).unwrap())
+    }
+
+    #[test]
+    fn emitters_are_tracked() {
+        let mut w = World::new(Light::new(Point::new(0.0, 100.0, 0.0), BLACK));
+        w.add_shape(Box::<Sphere>::default());
+        w.add_shape(glowing_sphere(3.0));
+        assert_eq!(w.get_emitters(), &w.keys[1..]);
+    }
+
+    #[test]
+    fn emitter_lights_estimate_sphere_irradiance() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let mut w = World::new(Light::new(Point::new(0.0, 100.0, 0.0), BLACK));
+        w.add_shape(glowing_sphere(3.0));
+        w.light_samples = 4000;
+        let point = Point::new(0.0, 0.0, 0.0);
+        let lights = w
+            .emitter_lights(&point, 0.0, &mut StdRng::seed_from_u64(7))
+            .unwrap();
+        let irradiance: f64 = lights
+            .iter()
+            .map(|l| l.intensity.rgb.x * (l.position - point).normalize().position.y)
+            .sum();
+        // a sphere of radius r at distance d casts pi * (r / d)^2 times its radiance
+        let expected = 1.0 / 9.0;
+        assert!(
+            (irradiance - expected).abs() < 0.05 * expected,
+            "{}",
+            irradiance
+        );
+    }
+
+    #[test]
+    fn emitters_light_and_shadow_the_scene() {
+        let mut w = World::new(Light::new(Point::new(0.0, 100.0, 0.0), BLACK));
+        w.light_samples = 256;
+        let floor = Material {
+            ambient: 0.0,
+            specular: 0.0,
+            ..Default::default()
+        };
+        let mut plane = Box::new(crate::features::shape::plane::Plane::default());
+        plane.set_material(floor).unwrap();
+        w.add_shape(plane);
+        w.add_shape(glowing_sphere(3.0));
+        let down = Vector::new(0.0, -1.0, 0.0);
+        let lit = w
+            .color_at(&Ray::new(Point::new(0.0, 1.0, 0.0), down))
+            .unwrap();
+        assert!(lit.rgb.x > 0.05, "{:?}", lit);
+        // the glowing sphere is seen at its own emission
+        let seen = w
+            .color_at(&Ray::new(Point::new(0.0, 10.0, 0.0), down))
+            .unwrap();
+        assert!(seen.rgb.x >= 1.0, "{:?}", seen);
+        // a blocker right under the light leaves the floor beneath it dark
+        w.add_shape(Box::new(
+            Sphere::new(
+                translation(0.0, 1.0, 0.0) * scaling(0.5, 0.1, 0.5),
+                Material::default(),
+            )
+            .unwrap(),
+        ));
+        let shadowed = w
+            .color_at(&Ray::new(Point::new(0.0, 0.5, 0.0), down))
+            .unwrap();
+        assert!(shadowed.rgb.x < 0.3 * lit.rgb.x, "{:?}", shadowed);
+    }
+
+    #[test]
+    fn occlusion_darkens_the_ambient_term() {
+        let mut w = World::new(Light::new(
+            Point::new(0.0, 10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_shape(Box::new(crate::features::shape::plane::Plane::default()));
+        // a lid just above the floor hides it from the light and from most of the sky
+        w.add_shape(Box::new(
+            Sphere::new(
+                translation(0.0, 0.2, 0.0) * scaling(10.0, 0.1, 10.0),
+                Material::default(),
+            )
+            .unwrap(),
+        ));
+        let ray = Ray::new(Point::new(0.0, 0.05, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(w.color_at(&ray).unwrap(), Color::new(0.1, 0.1, 0.1));
+        w.ambient_occlusion = Some(AmbientOcclusion::new(32, 1.0));
+        // only rays that skim out under the rim get away
+        assert!(w.color_at(&ray).unwrap().rgb.x < 0.02);
+    }
+
+    #[test]
+    fn misses_see_the_background() {
+        let mut w = World::default();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r).unwrap(), BLACK);
+        w.background = Background::Sky {
+            horizon: Color::new(1.0, 1.0, 1.0),
+            zenith: Color::new(0.2, 0.4, 1.0),
+        };
+        assert_eq!(w.color_at(&r).unwrap(), Color::new(0.2, 0.4, 1.0));
+    }
+
+    #[test]
+    fn fog_fades_hits_by_distance_and_hides_the_background() {
+        let mut w = World::default();
+        let hit = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let clear = w.color_at(&hit).unwrap();
+        let fog = Fog::new(Color::new(0.5, 0.5, 0.5), 0.1);
+        w.fog = Some(fog);
+        assert_eq!(w.color_at(&hit).unwrap(), fog.apply(clear, 4.0));
+        let miss = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&miss).unwrap(), fog.color);
+    }
+
+    #[test]
+    fn volumes_scatter_light_whichever_way_they_are_seen() {
+        use crate::features::shape::volume::Volume;
+        let w = World::new(Light::new(
+            Point::new(0.0, 10.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let volume = Volume::new(Box::<Sphere>::default(), 1.0, Material::default()).unwrap();
+        let material = volume.get_material();
+        for direction in [Vector::new(0.0, 0.0, 1.0), Vector::new(0.0, 1.0, 0.0)] {
+            let ray = Ray::new(Point::new(0.0, 0.0, 0.0), direction);
+            let i = Intersection::new(&volume.get_shape_id(), 0.5);
+            let comps = Computation::new(&ray, &i, &volume);
+            assert_eq!(comps.point, ray.position(0.5));
+            let c = w
+                .direct_light(&volume, &material, &comps, &mut StdRng::seed_from_u64(0))
+                .unwrap();
+            assert_eq!(c, Color::new(0.225, 0.225, 0.225));
+        }
+    }
 }
@@ -0,0 +1,118 @@
+mod features;
+
+pub use features::error::{Error, Result};
+
+pub mod math {
+    pub use crate::features::{
+        consts::EPSILON,
+        matrice::Matrice,
+        point::Point,
+        ray::Ray,
+        transformations::{
+            rotation_x, rotation_y, rotation_z, scaling, shearing, translation, view_transformation,
+        },
+        tuple::Tuple,
+        vector::Vector,
+    };
+}
+
+pub mod shapes {
+    pub use crate::features::{
+        computation::Computation,
+        intersection::{hit, sort_intersections, Intersection},
+        shape::{plane::Plane, sphere::Sphere, volume::Volume, Shape, SurfaceSample},
+    };
+}
+
+pub mod materials {
+    pub use crate::features::{
+        color::Color,
+        consts::{BLACK, WHITE},
+        material::{Material, Model},
+        pattern::{
+            blend::Blend,
+            checker::Checker,
+            gradient::Gradient,
+            perturbed::Perturbed,
+            radial_gradient::RadialGradient,
+            ring::Ring,
+            solid::Solid,
+            stripe::Stripe,
+            texture_map::{CubeMap, TextureMap},
+            uv::{
+                cylindrical_map, planar_map, spherical_map, AlignCheck, CubeFace, UvCheckers,
+                UvImage, UvMapping, UvPattern,
+            },
+            Pattern,
+        },
+        pbr::CookTorrance,
+    };
+}
+
+pub mod normals {
+    pub use crate::features::normal::{
+        bump::Bump, height_map::HeightMap, normal_map::NormalMap, NormalModifier,
+    };
+}
+
+pub mod noise {
+    pub use crate::features::noise::{fbm, perlin, turbulence};
+}
+
+pub mod stats {
+    pub use crate::features::stats::{measure, Counters, Stats};
+}
+
+pub mod world {
+    pub use crate::features::{
+        background::{Background, Environment},
+        fog::Fog,
+        light::{Light, Spot},
+        scene::Scene,
+        world::World,
+    };
+}
+
+pub mod animation {
+    pub use crate::features::animation::{
+        frame_times, Animation, CameraPath, Interpolation, Keyable, LightTracks, ShapeTracks, Track,
+    };
+}
+
+pub mod camera {
+    #[cfg(feature = "indicatif")]
+    pub use crate::features::progress::ProgressBar;
+    pub use crate::features::{
+        aov::{Aov, Frame},
+        camera::Camera,
+        checkpoint::Checkpoint,
+        control::{Progress, RenderControl, Stopped},
+        integrator::{Integrator, PathTracer},
+        occlusion::AmbientOcclusion,
+        progress::{ChannelProgress, ProgressObserver, ProgressUpdate, Silent},
+        tiles::{tiles, RenderEvent, Tile, TileOrder},
+    };
+}
+
+pub mod farm {
+    pub use crate::features::farm::{Coordinator, Job, Worker};
+}
+
+pub mod output {
+    pub use crate::features::canvas::Canvas;
+}
+
+pub mod prelude {
+    pub use crate::{
+        camera::Camera,
+        materials::{Color, Material, Pattern, BLACK, WHITE},
+        math::{
+            rotation_x, rotation_y, rotation_z, scaling, shearing, translation,
+            view_transformation, Matrice, Point, Ray, Vector,
+        },
+        output::Canvas,
+        shapes::{Plane, Shape, Sphere},
+        world::{Light, Scene, World},
+        Error, Result,
+    };
+}
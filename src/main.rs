@@ -1,8 +1,413 @@
-use practice::{draw_hexagonal_room, draw_sphere};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Instant,
+};
 
-mod features;
-mod practice;
-fn main() {
-    draw_sphere()
-    // draw_hexagonal_room()
+use cli::{Command, Format, IntegratorKind, Options};
+use rt::{
+    animation::{frame_times, Animation},
+    camera::{
+        AmbientOcclusion, Aov, Camera, Checkpoint, Integrator, PathTracer, RenderControl,
+        RenderEvent, Stopped,
+    },
+    farm::{Coordinator, Job, Worker},
+    output::Canvas,
+    stats,
+    world::{Scene, World},
+    Error,
+};
+
+mod cli;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match cli::parse(args.clone()) {
+        Ok(Command::Render(options)) => run(*options, args),
+        Ok(Command::Worker {
+            address,
+            threads,
+            stats,
+        }) => run_worker(&address, threads, stats),
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: Options, args: Vec<String>) -> Result<(), String> {
+    let loading = Instant::now();
+    let source = fs::read_to_string(&options.scene)
+        .map_err(|e| format!("{}: {}", options.scene.display(), e))?;
+    let (parsed, counters) = stats::measure(|| -> Result<_, String> {
+        let scene = Scene::parse_at(&source, &options.scene)
+            .map_err(|e| format!("{}: {}", options.scene.display(), e))?;
+        let camera = configure(scene.camera, &options)?;
+        Ok((scene.world, camera, scene.animation, scene.files))
+    });
+    let (mut world, mut camera, animation, files) = parsed?;
+    #[cfg(feature = "indicatif")]
+    if options.progress {
+        camera.progress = Box::new(rt::camera::ProgressBar::new());
+    }
+    use_threads(options.threads)?;
+    let loaded = (loading.elapsed(), counters);
+
+    let mut control = RenderControl::new();
+    control.time_budget = options.time_budget;
+    control.sample_budget = options.sample_budget;
+    let files = files
+        .into_iter()
+        .map(|file| match fs::read(&file) {
+            Ok(data) => Ok((file, data)),
+            Err(e) => Err(format!("{}: {}", file.display(), e)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(path) = &options.checkpoint {
+        // a scene posed at another time, or with an edited image, is another scene
+        let mut hashed = source.as_bytes().to_vec();
+        for (_, data) in &files {
+            hashed.extend(data);
+        }
+        if let Some(time) = options.time {
+            hashed.extend(time.to_le_bytes());
+        }
+        if let Some(shutter) = options.shutter {
+            hashed.extend(shutter.to_le_bytes());
+        }
+        let mut checkpoint = Checkpoint::for_scene(path, &hashed);
+        if let Some(interval) = options.checkpoint_interval {
+            checkpoint.interval = interval;
+        }
+        control.checkpoint = Some(checkpoint);
+    }
+    let aovs: Vec<Aov> = options.aovs.iter().map(|(aov, _)| *aov).collect();
+    // progressive renders keep the output file up to date so it can be watched
+    let on_event = |event: RenderEvent| {
+        if let (
+            RenderEvent::Pass {
+                pass,
+                passes,
+                image,
+            },
+            Some(path),
+        ) = (event, &options.output)
+        {
+            if pass + 1 < passes {
+                if let Err(e) = File::create(path)
+                    .and_then(|file| write_image(image, options.format, BufWriter::new(file)))
+                {
+                    eprintln!("warning: cannot write {}: {}", path.display(), e);
+                }
+            }
+        }
+    };
+    // an animation renders and writes its frames one after another, a still is one
+    // frame at the time asked for
+    let frames: Vec<(Option<usize>, Option<f64>)> = match options.frames {
+        Some((first, last)) => frame_times(first, last, options.fps)
+            .into_iter()
+            .enumerate()
+            .map(|(number, time)| (Some(number), Some(time)))
+            .collect(),
+        None => vec![(None, shutter_time(&options))],
+    };
+    let mut loaded = Some(loaded);
+    for (number, time) in frames {
+        if let Some(time) = time {
+            pose(&animation, &mut world, &mut camera, time, options.shutter)
+                .map_err(|e| e.to_string())?;
+        }
+        let start = Instant::now();
+        let mut frame = match &options.serve {
+            Some(address) => {
+                let mut coordinator = Coordinator::bind(address.as_str())
+                    .map_err(|e| format!("cannot listen on {}: {}", address, e))?;
+                if let Some(timeout) = options.farm_timeout {
+                    coordinator.timeout = timeout;
+                }
+                let job = Job {
+                    scene: source.clone(),
+                    args: args.clone(),
+                    files: files.clone(),
+                };
+                coordinator
+                    .render(&job, &camera, &on_event)
+                    .map_err(|e| e.to_string())?
+            }
+            None => camera
+                .render_with(&world, &aovs, &control, &on_event)
+                .map_err(|e| e.to_string())?,
+        };
+        let elapsed = start.elapsed();
+        if options.full_frame && options.region.is_some() {
+            let region = camera.region();
+            let full = |part: &Canvas| {
+                let mut image = Canvas::new(camera.hsize as usize, camera.vsize as usize);
+                image.paste(part, region.x, region.y);
+                image
+            };
+            frame.beauty = full(&frame.beauty);
+            for (_, pass) in frame.passes.iter_mut() {
+                *pass = full(pass);
+            }
+        }
+        let image = frame.beauty;
+
+        let writing = Instant::now();
+        let written = match &options.output {
+            Some(path) => File::create(numbered(path, number))
+                .and_then(|file| write_image(&image, options.format, BufWriter::new(file)))
+                .map_err(|e| format!("cannot write {}: {}", path.display(), e)),
+            None => write_image(&image, options.format, io::stdout().lock())
+                .map_err(|e| format!("cannot write image to stdout: {}", e)),
+        };
+        written?;
+        for ((aov, path), (_, pass)) in options.aovs.iter().zip(&frame.passes) {
+            // distances are scaled so the furthest hit is white
+            let pass = match aov {
+                Aov::Depth => normalized(pass),
+                _ => pass.clone(),
+            };
+            let format = Format::from_path(path).unwrap_or(options.format);
+            let path = numbered(path, number);
+            File::create(&path)
+                .and_then(|file| write_image(&pass, format, BufWriter::new(file)))
+                .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+        }
+
+        if options.stats {
+            let progress = frame.progress;
+            let mut stats = frame.stats;
+            // loading counts towards the first frame
+            if let Some((time, counters)) = loaded.take() {
+                stats.phases.insert(0, ("load", time));
+                stats.counters += counters;
+            }
+            stats.phases.push(("write", writing.elapsed()));
+            let counters = stats.counters;
+            if let (Some(number), Some(time)) = (number, time) {
+                eprintln!("frame {} at {}s:", number, time);
+            }
+            eprintln!(
+                "rendered {}x{} at {} spp in {:.2?}",
+                image.width, image.height, progress.samples, elapsed
+            );
+            if let Some(reason) = progress.stopped {
+                let reason = match reason {
+                    Stopped::Cancelled => "cancelled",
+                    Stopped::OutOfTime => "out of time",
+                };
+                eprintln!(
+                    "  stopped:            {} after {} full passes, {} of {} pixels sampled",
+                    reason,
+                    progress.passes,
+                    progress.pixels,
+                    image.width * image.height
+                );
+            }
+            eprintln!("  objects:            {}", world.objects.len());
+            eprintln!("  threads:            {}", rayon::current_num_threads());
+            let rays = counters.primary_rays + counters.shadow_rays + counters.secondary_rays;
+            eprintln!(
+                "  rays:               {} ({:.0} rays/s)",
+                rays,
+                rays as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+            );
+            eprintln!("    primary:          {}", counters.primary_rays);
+            eprintln!("    shadow:           {}", counters.shadow_rays);
+            eprintln!("    secondary:        {}", counters.secondary_rays);
+            eprintln!("  intersection tests: {}", counters.intersection_tests());
+            for (kind, tests) in &counters.tests {
+                let kind = format!("{}:", kind.to_lowercase());
+                eprintln!("    {:<18}{}", kind, tests);
+            }
+            eprintln!("  matrix inversions:  {}", counters.inversions);
+            let phases: Vec<String> = stats
+                .phases
+                .iter()
+                .map(|(name, time)| format!("{} {:.2?}", name, time))
+                .collect();
+            eprintln!("  phases:             {}", phases.join(", "));
+        }
+    }
+    Ok(())
+}
+
+// applies the command line's camera settings over the scene's
+fn configure(mut camera: Camera, options: &Options) -> Result<Camera, String> {
+    if options.width.is_some() || options.height.is_some() || options.fov.is_some() {
+        let mut resized = Camera::new(
+            options.width.map_or(camera.hsize, |w| w as f64),
+            options.height.map_or(camera.vsize, |h| h as f64),
+            options.fov.map_or(camera.field_of_view, f64::to_radians),
+        );
+        resized
+            .set_transform(camera.get_transform())
+            .map_err(|e| e.to_string())?;
+        resized.samples = camera.samples;
+        resized.seed = camera.seed;
+        resized.integrator = camera.integrator;
+        camera = resized;
+    }
+    if let Some(samples) = options.samples {
+        camera.samples = samples;
+    }
+    match options.integrator {
+        Some(IntegratorKind::Whitted) => camera.integrator = Integrator::Whitted,
+        Some(IntegratorKind::Path) if !matches!(camera.integrator, Integrator::PathTracer(_)) => {
+            camera.integrator = Integrator::PathTracer(PathTracer::default())
+        }
+        Some(IntegratorKind::AmbientOcclusion)
+            if !matches!(camera.integrator, Integrator::AmbientOcclusion(_)) =>
+        {
+            camera.integrator = Integrator::AmbientOcclusion(AmbientOcclusion::default())
+        }
+        _ => {}
+    }
+    if let Some(seed) = options.seed {
+        camera.seed = seed;
+    }
+    if let Some(size) = options.tile_size {
+        camera.tile_size = size;
+    }
+    if let Some(order) = options.tile_order {
+        camera.tile_order = order;
+    }
+    camera.progressive = options.progressive;
+    if let Some(region) = &options.region {
+        cli::check_region(region, camera.hsize as usize, camera.vsize as usize)?;
+    }
+    camera.region = options.region;
+    // farmed tiles are rendered without a render control, so the budget caps the samples
+    if let (Some(_), Some(budget)) = (&options.serve, options.sample_budget) {
+        camera.samples = camera.samples.clamp(1, budget);
+    }
+    Ok(camera)
+}
+
+fn use_threads(threads: Option<usize>) -> Result<(), String> {
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| format!("cannot start {} render threads: {}", threads, e))?;
+    }
+    Ok(())
+}
+
+// a still is posed at the time asked for, and an open shutter needs a time to open at
+fn shutter_time(options: &Options) -> Option<f64> {
+    options.time.or(options.shutter.map(|_| 0.0))
+}
+
+fn pose(
+    animation: &Animation,
+    world: &mut World,
+    camera: &mut Camera,
+    time: f64,
+    shutter: Option<f64>,
+) -> rt::Result<()> {
+    match shutter {
+        Some(shutter) => animation.apply_shutter(world, camera, time, time + shutter),
+        None => animation.apply(world, camera, time),
+    }
+}
+
+// renders tiles for a coordinator, setting the scene up from the command line and the
+// files it sends
+fn run_worker(address: &str, threads: Option<usize>, stats: bool) -> Result<(), String> {
+    use_threads(threads)?;
+    let start = Instant::now();
+    let tiles = Worker::connect(address)
+        .and_then(|worker| {
+            let options = match cli::parse(worker.job.args.clone()) {
+                Ok(Command::Render(options)) => options,
+                _ => {
+                    return Err(Error::parse(
+                        "the coordinator sent an unusable command line",
+                    ))
+                }
+            };
+            let Scene {
+                mut world,
+                camera,
+                animation,
+                ..
+            } = Scene::parse_with_files(&worker.job.scene, &options.scene, &worker.job.files)?;
+            let mut camera = configure(camera, &options).map_err(Error::parse)?;
+            if let Some(time) = shutter_time(&options) {
+                pose(&animation, &mut world, &mut camera, time, options.shutter)?;
+            }
+            worker.render(&world, camera)
+        })
+        .map_err(|e| format!("{}: {}", address, e))?;
+    if stats {
+        eprintln!("rendered {} tiles in {:.2?}", tiles, start.elapsed());
+    }
+    Ok(())
+}
+
+// the path of a numbered frame: a run of '#' becomes the number padded to its length,
+// otherwise the number goes before the extension
+fn numbered(path: &Path, number: Option<usize>) -> PathBuf {
+    let Some(number) = number else {
+        return path.to_path_buf();
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = match name.find('#') {
+        Some(start) => {
+            let width = name[start..].chars().take_while(|c| *c == '#').count();
+            format!(
+                "{}{:0width$}{}",
+                &name[..start],
+                number,
+                &name[start + width..],
+                width = width
+            )
+        }
+        None => match name.rsplit_once('.') {
+            Some((stem, extension)) => format!("{}-{:04}.{}", stem, number, extension),
+            None => format!("{}-{:04}", name, number),
+        },
+    };
+    path.with_file_name(name)
+}
+
+fn normalized(image: &Canvas) -> Canvas {
+    let furthest = image
+        .canvas
+        .iter()
+        .flatten()
+        .map(|c| c.rgb.x)
+        .fold(0.0, f64::max);
+    let mut scaled = image.clone();
+    if furthest > 0.0 {
+        for color in scaled.canvas.iter_mut().flatten() {
+            *color = *color * (1.0 / furthest);
+        }
+    }
+    scaled
+}
+
+fn write_image<W: Write>(image: &Canvas, format: Format, mut writer: W) -> io::Result<()> {
+    match format {
+        Format::Ppm => image.write_ppm(&mut writer)?,
+        Format::Bmp => image.write_bmp(&mut writer)?,
+    }
+    writer.flush()
 }